@@ -10,11 +10,11 @@ fn ackermann_2ary_iter(m: u128, n: u128) -> u128 {
             (Some(result), None) => return result,
             // If m == 0
             // r1: A(0, n) => n + 1
-            (Some(n), Some(m)) if m == 0 => stack.push(n + 1),
+            (Some(n), Some(0)) => stack.push(n + 1),
             // If n == 0
             // r2: A(m + 1, 0) => A(m, 1)
             // r2: A(m, 0) => A(m - 1, 1)
-            (Some(n), Some(m)) if n == 0 => {
+            (Some(0), Some(m)) => {
                 // Push m first
                 stack.push(m - 1);
                 // Push n
@@ -0,0 +1,10 @@
+use dynamodb_sheep_shed::DynamoDBSheepShed;
+
+use lambda_apigw_utils::prelude::*;
+
+async fn retrieve_history(req: SimpleRequest<'_>) -> SimpleResult {
+    sheep_shed_handlers::retrieve_history(req, |shed_id| DynamoDBSheepShed::new(dynamo(), shed_id))
+        .await
+}
+
+lambda_main!(async retrieve_history, dynamo = aws_sdk_dynamodb::Client);
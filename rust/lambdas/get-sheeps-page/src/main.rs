@@ -0,0 +1,9 @@
+use dynamodb_sheep_shed::DynamoDBSheepShed;
+
+use lambda_apigw_utils::prelude::*;
+
+async fn list_sheeps(req: SimpleRequest<'_>) -> SimpleResult {
+    sheep_shed_handlers::list_sheeps(req, |shed_id| DynamoDBSheepShed::new(dynamo(), shed_id)).await
+}
+
+lambda_main!(async list_sheeps, dynamo = aws_sdk_dynamodb::Client);
@@ -0,0 +1,10 @@
+use dynamodb_sheep_shed::DynamoDBSheepShed;
+
+use lambda_apigw_utils::prelude::*;
+
+async fn transfer_sheep(req: SimpleRequest<'_>) -> SimpleResult {
+    sheep_shed_handlers::transfer_sheep(req, |shed_id| DynamoDBSheepShed::new(dynamo(), shed_id))
+        .await
+}
+
+lambda_main!(async transfer_sheep, dynamo = aws_sdk_dynamodb::Client);
@@ -0,0 +1,10 @@
+use dynamodb_sheep_shed::DynamoDBSheepShed;
+
+use lambda_apigw_utils::prelude::*;
+
+async fn insert_sheeps(req: SimpleRequest<'_>) -> SimpleResult {
+    sheep_shed_handlers::insert_sheeps(req, |shed_id| DynamoDBSheepShed::new(dynamo(), shed_id))
+        .await
+}
+
+lambda_main!(async insert_sheeps, dynamo = aws_sdk_dynamodb::Client);
@@ -0,0 +1,9 @@
+use dynamodb_sheep_shed::DynamoDBSheepShed;
+
+use lambda_apigw_utils::prelude::*;
+
+async fn weigh_sheep(req: SimpleRequest<'_>) -> SimpleResult {
+    sheep_shed_handlers::weigh_sheep(req, |shed_id| DynamoDBSheepShed::new(dynamo(), shed_id)).await
+}
+
+lambda_main!(async weigh_sheep, dynamo = aws_sdk_dynamodb::Client);
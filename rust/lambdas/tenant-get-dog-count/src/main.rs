@@ -0,0 +1,17 @@
+use dynamodb_sheep_shed::TenantSheepShed;
+use sheep_shed::TenantId;
+
+use lambda_apigw_utils::prelude::*;
+
+async fn bark_answer(req: SimpleRequest<'_>) -> SimpleResult {
+    let tenant_id: TenantId = extract_tenant_id(req.cognito_values.as_ref())?;
+
+    log::info!("tenant_id={tenant_id}");
+
+    sheep_shed_handlers::bark_answer(req, |shed_id| {
+        TenantSheepShed::new(dynamo(), tenant_id.clone(), shed_id)
+    })
+    .await
+}
+
+auth_lambda_main!(async bark_answer, dynamo = aws_sdk_dynamodb::Client);
@@ -0,0 +1,50 @@
+//! Provision and migrate the table of the sheds
+//!
+//! Usage: `sheep-table [ensure|migrate|version]...`, `ensure migrate` by default.
+//!
+//! The table is configured by the same environment variables as the lambdas
+//! (see [SheepTable::from_env]) and the AWS credentials and region by the
+//! usual AWS environment variables or profile.
+
+use dynamodb_sheep_shed::SheepTable;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let commands = std::env::args().skip(1).collect::<Vec<_>>();
+    let commands = if commands.is_empty() {
+        vec!["ensure".to_owned(), "migrate".to_owned()]
+    } else {
+        commands
+    };
+
+    let client = aws_sdk_dynamodb::Client::new(&aws_config::load_from_env().await);
+    let table = SheepTable::from_env(client)?;
+    for command in commands {
+        match command.as_str() {
+            "ensure" => {
+                let changes = table.ensure_table().await?;
+                println!("{}: {changes:?}", table.table_name());
+            }
+            "migrate" => {
+                for migration in table.migrate().await? {
+                    println!(
+                        "{}: migrated to version {} ({})",
+                        table.table_name(),
+                        migration.version,
+                        migration.description
+                    );
+                }
+            }
+            "version" => {
+                let version = table.schema_version().await?;
+                println!("{}: schema version {version}", table.table_name());
+            }
+            _ => Err(format!(
+                "unknown command {command:?}, expected `ensure`, `migrate` or `version`"
+            ))?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! The construction of a [DynamoDBSheepShed], see [DynamoDBSheepShedBuilder]
+
+use std::{str::FromStr, time::Duration};
+
+use aws_sdk_dynamodb::Client;
+use sheep_shed::ShedId;
+
+use crate::{
+    DynamoDBSheepShed, RetryConfig, ScanConfig, SheepCountMode, DEFAULT_KEY_ATTRIBUTE,
+    MIN_CURSOR_KEY_LEN,
+};
+
+/// Read and parse the environment variable `name`, [None] if it is not set
+/// # Errors
+/// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the
+/// variable is set but is not `expected`.
+pub(crate) fn env_var<T: FromStr>(
+    name: &str,
+    expected: &str,
+) -> Result<Option<T>, sheep_shed::errors::Error> {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            log::info!("{name}={value}");
+            value.parse().map_err(|_| {
+                sheep_shed::errors::Error::InvalidConfiguration(format!(
+                    "`{name}` must be {expected}, got {value:?}"
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// The error of an environment variable `name` that is set to a value that is not `expected`
+pub(crate) fn invalid(name: &str, expected: &str) -> sheep_shed::errors::Error {
+    sheep_shed::errors::Error::InvalidConfiguration(format!("`{name}` must be {expected}"))
+}
+
+/// A builder of [DynamoDBSheepShed]
+///
+/// The [Client], the table name and the [ShedId] are mandatory, everything else
+/// has a default: the [DEFAULT_KEY_ATTRIBUTE], no cursor key (so the shed cannot
+/// be listed), eventually consistent reads, no tombstones, [SheepCountMode::Scan],
+/// the default [ScanConfig] and the default [RetryConfig].
+#[derive(Debug, Clone, Default)]
+pub struct DynamoDBSheepShedBuilder {
+    client: Option<Client>,
+    table_name: Option<String>,
+    shed_id: Option<ShedId>,
+    key_attribute: Option<String>,
+    cursor_key: Option<Vec<u8>>,
+    consistent_read: bool,
+    tombstone_retention: Option<Duration>,
+    killer: Option<String>,
+    sheep_count_mode: SheepCountMode,
+    scan_config: ScanConfig,
+    retry_config: RetryConfig,
+}
+
+impl DynamoDBSheepShedBuilder {
+    /// Creates an empty [DynamoDBSheepShedBuilder]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a [DynamoDBSheepShedBuilder] configured by the environment variables:
+    /// - `BACKEND_TABLE_NAME`, mandatory, the table name
+    /// - `BACKEND_TABLE_KEY_ATTRIBUTE`, the name of the hash key of the table
+    /// - `CURSOR_SIGNING_KEY`, the secret used to sign the [sheep_shed::Cursor]s, at
+    ///   least [MIN_CURSOR_KEY_LEN] bytes long, see [DynamoDBSheepShed::with_cursor_key]
+    /// - `CONSISTENT_READ`, `true` or `false`, see [DynamoDBSheepShedBuilder::with_consistent_read]
+    /// - `TOMBSTONE_RETENTION_DAYS`, switch to tombstone mode (see
+    ///   [DynamoDBSheepShed::with_tombstones]) with the `AWS_LAMBDA_FUNCTION_NAME`
+    ///   as the killer
+    /// - `SHEEP_COUNT_MODE`, `scan` or `counter`, see [SheepCountMode]
+    /// - `SCAN_*`, see [ScanConfig::from_env]
+    /// - `RETRY_*`, see [RetryConfig::from_env]
+    ///
+    /// The [Client] and the [ShedId] still have to be given.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if
+    /// `BACKEND_TABLE_NAME` is not set or if a variable has an invalid value.
+    pub fn from_env() -> Result<Self, sheep_shed::errors::Error> {
+        let table_name = std::env::var("BACKEND_TABLE_NAME").map_err(|_| {
+            sheep_shed::errors::Error::InvalidConfiguration(
+                "Mandatory environment variable `BACKEND_TABLE_NAME` is not set".to_owned(),
+            )
+        })?;
+        log::info!("BACKEND_TABLE_NAME={table_name}");
+        let builder = Self::new()
+            .with_table_name(table_name)
+            .with_scan_config(ScanConfig::from_env()?)
+            .with_retry_config(RetryConfig::from_env()?);
+        let builder = match env_var::<String>("BACKEND_TABLE_KEY_ATTRIBUTE", "an attribute name")? {
+            Some(key_attribute) => builder.with_key_attribute(key_attribute),
+            None => builder,
+        };
+        // Not logged, unlike the other variables: it is a secret
+        let builder = match std::env::var("CURSOR_SIGNING_KEY") {
+            Ok(cursor_key) => builder.with_cursor_key(cursor_key),
+            Err(_) => {
+                log::warn!("CURSOR_SIGNING_KEY is not set, the shed cannot be listed");
+                builder
+            }
+        };
+        let builder = match env_var("CONSISTENT_READ", "`true` or `false`")? {
+            Some(consistent_read) => builder.with_consistent_read(consistent_read),
+            None => builder,
+        };
+        let builder = match std::env::var("AWS_LAMBDA_FUNCTION_NAME") {
+            Ok(killer) => builder.with_killer(killer),
+            Err(_) => builder,
+        };
+        let builder = match env_var::<u64>("TOMBSTONE_RETENTION_DAYS", "a number of days")? {
+            Some(days) => builder.with_tombstones(Duration::from_secs(days * 24 * 3600)),
+            None => builder,
+        };
+        Ok(match env_var("SHEEP_COUNT_MODE", "`scan` or `counter`")? {
+            Some(mode) => builder.with_sheep_count_mode(mode),
+            None => builder,
+        })
+    }
+
+    /// The [Client] used to reach DynamoDB
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// The name of the table holding the sheds
+    pub fn with_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = Some(table_name.into());
+        self
+    }
+
+    /// The [ShedId] of the shed
+    pub fn with_shed_id(mut self, shed_id: ShedId) -> Self {
+        self.shed_id = Some(shed_id);
+        self
+    }
+
+    /// The name of the hash key of the table, of type `N`, holding the [sheep_shed::Tattoo]s
+    pub fn with_key_attribute(mut self, key_attribute: impl Into<String>) -> Self {
+        self.key_attribute = Some(key_attribute.into());
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_cursor_key], it must be at least
+    /// [MIN_CURSOR_KEY_LEN] bytes long
+    pub fn with_cursor_key(mut self, cursor_key: impl Into<Vec<u8>>) -> Self {
+        self.cursor_key = Some(cursor_key.into());
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_consistent_read]
+    pub fn with_consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_tombstones]
+    pub fn with_tombstones(mut self, retention: Duration) -> Self {
+        self.tombstone_retention = Some(retention);
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_killer]
+    pub fn with_killer(mut self, killer: impl Into<String>) -> Self {
+        self.killer = Some(killer.into());
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_sheep_count_mode]
+    pub fn with_sheep_count_mode(mut self, sheep_count_mode: SheepCountMode) -> Self {
+        self.sheep_count_mode = sheep_count_mode;
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_scan_config]
+    pub fn with_scan_config(mut self, scan_config: ScanConfig) -> Self {
+        self.scan_config = scan_config;
+        self
+    }
+
+    /// See [DynamoDBSheepShed::with_retry_config]
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Build the [DynamoDBSheepShed]
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the
+    /// [Client], the table name or the [ShedId] is missing, if the table name
+    /// or the key attribute is empty, or if the cursor key is shorter than
+    /// [MIN_CURSOR_KEY_LEN] bytes.
+    pub fn build(self) -> Result<DynamoDBSheepShed, sheep_shed::errors::Error> {
+        let missing = |what: &str| {
+            sheep_shed::errors::Error::InvalidConfiguration(format!("the {what} is missing"))
+        };
+        let client = self.client.ok_or_else(|| missing("client"))?;
+        let table_name = self
+            .table_name
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| missing("table name"))?;
+        let shed_id = self.shed_id.ok_or_else(|| missing("shed id"))?;
+        let key_attribute = match self.key_attribute {
+            Some(key_attribute) if key_attribute.is_empty() => Err(missing("key attribute"))?,
+            Some(key_attribute) => key_attribute,
+            None => DEFAULT_KEY_ATTRIBUTE.to_owned(),
+        };
+        if self
+            .cursor_key
+            .as_ref()
+            .is_some_and(|cursor_key| cursor_key.len() < MIN_CURSOR_KEY_LEN)
+        {
+            Err(sheep_shed::errors::Error::InvalidConfiguration(format!(
+                "the cursor key must be at least {MIN_CURSOR_KEY_LEN} bytes long"
+            )))?
+        }
+        Ok(DynamoDBSheepShed {
+            client,
+            table_name,
+            shed_id,
+            key_attribute,
+            cursor_key: self.cursor_key,
+            consistent_read: self.consistent_read,
+            tombstone_retention: self.tombstone_retention,
+            killer: self.killer,
+            sheep_count_mode: self.sheep_count_mode,
+            scan_config: self.scan_config,
+            retry_config: self.retry_config,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+//! The sheep counter of a shed, see [SheepCountMode]
+
+use std::str::FromStr;
+
+use aws_sdk_dynamodb::{operation::update_item::UpdateItemError, types::AttributeValue};
+use serde::Serialize;
+use serde_dynamo::aws_sdk_dynamodb_1::from_attribute_value;
+
+use crate::{database_error, DynamoDBSheepShed, SHEEP_COUNT_ATTRIBUTE};
+
+/// How a [DynamoDBSheepShed] answers [sheep_shed::AsyncSheepShed::sheep_count]
+///
+/// Each write adding or removing a sheep also updates the counter item of its shed
+/// in the same transaction (see [crate::SHEEP_COUNTER_SORT_KEY]), so the counter
+/// is always maintained, whatever the mode. A table filled before the
+/// counters existed needs the migration 2 of the [crate::SheepTable] (or a
+/// [DynamoDBSheepShed::reconcile_sheep_count] of each shed) first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SheepCountMode {
+    /// Count the sheeps with a parallel scan of the whole table, which costs
+    /// read capacity proportional to the size of the table
+    #[default]
+    Scan,
+    /// Read the counter of the shed with one `GetItem`
+    Counter,
+}
+
+impl FromStr for SheepCountMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scan" => Ok(Self::Scan),
+            "counter" => Ok(Self::Counter),
+            _ => Err(format!("unknown sheep count mode {s:?}")),
+        }
+    }
+}
+
+/// The result of a [DynamoDBSheepShed::reconcile_sheep_count]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SheepCountDrift {
+    /// The value of the counter before the reconciliation
+    pub counter: i64,
+    /// The number of sheeps found by the scan, the new value of the counter
+    pub scanned: usize,
+}
+
+impl SheepCountDrift {
+    /// How many sheeps the counter had too many (or too few if negative)
+    pub fn drift(&self) -> i64 {
+        self.counter - self.scanned as i64
+    }
+}
+
+impl DynamoDBSheepShed {
+    /// Read the counter of the shed, 0 if it does not exist yet
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::GenericError] if the counter
+    /// item does not hold an integer.
+    pub(crate) async fn _read_sheep_counter(&self) -> Result<i64, sheep_shed::errors::Error> {
+        let get_item = self
+            .client
+            .get_item()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_counter_key()))
+            .consistent_read(true);
+        self.retry_config
+            .call("get_item", || get_item.clone().send())
+            .await
+            .map_err(database_error)?
+            .item
+            .and_then(|mut item| item.remove(SHEEP_COUNT_ATTRIBUTE))
+            .map_or(Ok(0), |count| {
+                from_attribute_value(count).map_err(|e| {
+                    let err_string = format!("invalid counter for shed {}: {e}", self.shed_id);
+                    log::error!("{err_string}");
+                    sheep_shed::errors::Error::GenericError(err_string)
+                })
+            })
+    }
+
+    /// Recompute the counter of the shed from a scan of the whole table and report
+    /// how far it had drifted.
+    ///
+    /// The counter is only overwritten if it did not change during the scan, so
+    /// the reconciliation is best run when the shed is quiet.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::GenericError] if the counter
+    /// changed during the scan.
+    pub async fn reconcile_sheep_count(
+        &self,
+    ) -> Result<SheepCountDrift, sheep_shed::errors::Error> {
+        log::info!("reconcile_sheep_count()");
+        let counter = self._read_sheep_counter().await?;
+        let scanned = self._scan_sheep_count().await?;
+        let drift = SheepCountDrift { counter, scanned };
+        if drift.drift() != 0 {
+            log::warn!(
+                "the counter of shed {} drifted by {}: {counter} instead of {scanned}",
+                self.shed_id,
+                drift.drift()
+            );
+            let update_item = self
+                .client
+                .update_item()
+                .table_name(self.table_name.as_str())
+                .set_key(Some(self.sheep_counter_key()))
+                .update_expression("SET #count = :scanned")
+                .condition_expression("attribute_not_exists(#count) OR #count = :counter")
+                .expression_attribute_names("#count", SHEEP_COUNT_ATTRIBUTE)
+                .expression_attribute_values(":scanned", AttributeValue::N(scanned.to_string()))
+                .expression_attribute_values(":counter", AttributeValue::N(counter.to_string()));
+            self.retry_config
+                .call("update_item", || update_item.clone().send())
+                .await
+                .map_err(|e| match e.as_service_error() {
+                    Some(UpdateItemError::ConditionalCheckFailedException(_)) => {
+                        let err_string = "the counter changed during the reconciliation";
+                        log::error!("{err_string}");
+                        sheep_shed::errors::Error::GenericError(err_string.to_owned())
+                    }
+                    _ => database_error(e),
+                })?;
+        }
+        log::info!("reconcile_sheep_count => Ok({drift:?})");
+        Ok(drift)
+    }
+}
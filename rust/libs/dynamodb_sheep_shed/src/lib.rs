@@ -1,211 +1,1363 @@
-use std::cmp::min;
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use aws_sdk_dynamodb::{
-    error::ProvideErrorMetadata,
-    operation::{delete_item::DeleteItemError, put_item::PutItemError},
-    types::{ReturnValue, Select},
+    config::http::HttpResponse,
+    error::{ProvideErrorMetadata, SdkError},
+    operation::{query::QueryError, transact_write_items::TransactWriteItemsError},
+    types::{
+        AttributeValue, Delete, DeleteRequest, Put, PutRequest,
+        ReturnValuesOnConditionCheckFailure, TransactWriteItem, Update, WriteRequest,
+    },
     Client,
 };
-use serde_dynamo::{aws_sdk_dynamodb_1::from_item, to_attribute_value, to_item};
-use sheep_shed::{Sheep, SheepShed, Tattoo};
+pub use builder::DynamoDBSheepShedBuilder;
+use chrono::SecondsFormat;
+pub use counter::{SheepCountDrift, SheepCountMode};
+use futures::stream::{self, Stream, TryStreamExt};
+use retry::database_error;
+pub use retry::RetryConfig;
+pub use scan::{ScanConfig, ScanProjection, DEFAULT_MAX_CONCURRENT_SEGMENTS};
+pub use schema::{
+    Migration, SheepTable, TableChange, MIGRATIONS, SCHEMA_VERSION_ATTRIBUTE,
+    SCHEMA_VERSION_SORT_KEY,
+};
+use serde::{Deserialize, Serialize};
+use serde_dynamo::{
+    aws_sdk_dynamodb_1::{from_attribute_value, from_item},
+    to_attribute_value, to_item,
+};
+use sheep_shed::{
+    AddSheepOutcome, AsyncSheepShed, Cursor, ShedId, Sheep, SheepPage, SheepShed, Tattoo, WeighIn,
+    Weight, WeightCriteria,
+};
+pub use tenant::TenantSheepShed;
+pub use tombstones::{KilledPage, KilledSheep};
+
+mod builder;
+mod counter;
+mod retry;
+mod scan;
+mod schema;
+mod tenant;
+mod tombstones;
+
+/// The maximum number of items DynamoDB accepts in one transaction
+pub const TRANSACT_WRITE_MAX_ITEMS: usize = 100;
+/// The maximum number of requests DynamoDB accepts in one `BatchWriteItem`
+pub const BATCH_WRITE_MAX_ITEMS: usize = 25;
+/// The number of attempts of a transaction that makes no progress before giving up
+pub const TRANSACT_WRITE_MAX_ATTEMPTS: u32 = 5;
+/// The name of the Global Secondary Index used by [AsyncSheepShed::heaviest_matching]
+///
+/// Its partition key is [SHED_PRIME_WEIGHT_ATTRIBUTE], of type `S`, and its sort
+/// key is `weight`, of type `N`, and it projects all the attributes. Items written
+/// without the [SHED_PRIME_WEIGHT_ATTRIBUTE] are absent from the index.
+pub const WEIGHT_INDEX_NAME: &str = "shed-weight-index";
+/// The precomputed attribute made of the [ShedId] of a [Sheep] followed by `#1`
+/// if its `weight` expressed in micrograms is a prime number or `#0` if not
+/// (e.g. `farm-1#1`), so each shed has its own partitions in the [WEIGHT_INDEX_NAME]
+pub const SHED_PRIME_WEIGHT_ATTRIBUTE: &str = "shed_prime_weight";
+/// The default name of the hash key of the table, of type `N`, holding the [Tattoo]
+/// of the [Sheep] of each item (see [DynamoDBSheepShedBuilder::with_key_attribute])
+pub const DEFAULT_KEY_ATTRIBUTE: &str = "tattoo";
+/// The sort key of the table, of type `S`
+///
+/// All the items of a [Sheep] live in the partition of its [Tattoo] and their sort
+/// key starts with the [ShedId] of their shed followed by `#`: the [Sheep] itself
+/// is under [SHEEP_SORT_KEY] and each of its [WeighIn]s under
+/// [WEIGH_IN_SORT_KEY_PREFIX] followed by its timestamp, so the weight history
+/// is naturally sorted from the oldest to the newest [WeighIn].
+///
+/// The [Sheep]s of different sheds can therefore share a [Tattoo] in the same
+/// table. Items written before the sheds existed have no [ShedId] prefix and
+/// belong to no shed.
+pub const SORT_KEY_ATTRIBUTE: &str = "sk";
+/// The [SORT_KEY_ATTRIBUTE] value of the item of a [Sheep], after the [ShedId] prefix
+pub const SHEEP_SORT_KEY: &str = "sheep";
+/// The [SORT_KEY_ATTRIBUTE] prefix of the items of the [WeighIn]s of a [Sheep],
+/// after the [ShedId] prefix
+pub const WEIGH_IN_SORT_KEY_PREFIX: &str = "weigh_in#";
+/// The attribute holding the `version` of a [Sheep], absent until its first
+/// modification
+pub const VERSION_ATTRIBUTE: &str = "version";
+/// The attribute holding the time a [Sheep] was killed, only present on the
+/// tombstones left by a [DynamoDBSheepShed] in tombstone mode
+/// (see [DynamoDBSheepShed::with_tombstones])
+pub const KILLED_AT_ATTRIBUTE: &str = "killed_at";
+/// The name of the Global Secondary Index used by [DynamoDBSheepShed::list_killed]
+///
+/// Its partition key is [SHED_KILLED_ATTRIBUTE], of type `S`, and its sort key is
+/// [KILLED_AT_ATTRIBUTE], of type `S`, and it projects all the attributes. Only the
+/// tombstones have the [SHED_KILLED_ATTRIBUTE], so only they are in the index.
+pub const KILLED_INDEX_NAME: &str = "shed-killed-index";
+/// The attribute holding the [ShedId] of a tombstone, so the tombstones of each
+/// shed have their own partition in the [KILLED_INDEX_NAME]
+pub const SHED_KILLED_ATTRIBUTE: &str = "shed_killed";
+/// The attribute holding who killed a [Sheep], see [DynamoDBSheepShed::with_killer]
+pub const KILLED_BY_ATTRIBUTE: &str = "killed_by";
+/// The attribute holding the time (in seconds since the epoch) after which a
+/// tombstone and its [WeighIn]s can be purged, it must be the TTL attribute of the table
+pub const EXPIRE_AT_ATTRIBUTE: &str = "expire_at";
+/// The minimum length, in bytes, of the secret signing the [Cursor]s, see
+/// [DynamoDBSheepShedBuilder::with_cursor_key]
+pub const MIN_CURSOR_KEY_LEN: usize = 32;
+/// The [SORT_KEY_ATTRIBUTE] value of the counter item of a shed, after the [ShedId]
+/// prefix. The counter items live in the partition of the [SHEEP_COUNTER_TATTOO]
+/// and hold the number of living [Sheep]s of their shed in the [SHEEP_COUNT_ATTRIBUTE].
+pub const SHEEP_COUNTER_SORT_KEY: &str = "sheep_count";
+/// The [Tattoo] of the partition of the counter items, it can still be given to a
+/// [Sheep] as the sort keys differ
+pub const SHEEP_COUNTER_TATTOO: Tattoo = Tattoo(0);
+/// The attribute of a counter item holding the number of living [Sheep]s of its shed
+pub const SHEEP_COUNT_ATTRIBUTE: &str = "sheep_count";
+
+/// The value of the [SHED_PRIME_WEIGHT_ATTRIBUTE] for a [Weight] in the given shed
+fn shed_prime_weight_value(shed_id: &impl std::fmt::Display, weight: &Weight) -> AttributeValue {
+    AttributeValue::S(format!(
+        "{shed_id}#{}",
+        if weight.is_prime_ug() { "1" } else { "0" }
+    ))
+}
+
+/// The [SORT_KEY_ATTRIBUTE] value of the item of a [Sheep] in the given shed
+fn sheep_sort_key(shed_id: &ShedId) -> AttributeValue {
+    AttributeValue::S(format!("{shed_id}#{SHEEP_SORT_KEY}"))
+}
+
+/// The [SORT_KEY_ATTRIBUTE] prefix of the items of the [WeighIn]s in the given shed
+fn weigh_in_sort_key_prefix(shed_id: &ShedId) -> String {
+    format!("{shed_id}#{WEIGH_IN_SORT_KEY_PREFIX}")
+}
+
+/// The position of [AsyncSheepShed::list_page] in the partitions of the shed in
+/// the [WEIGHT_INDEX_NAME], the payload of its [Cursor]s
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PagePosition {
+    /// `true` in the partition of the prime weights, listed after the other one
+    prime: bool,
+    /// The [Tattoo] and the [Weight] of the last [Sheep] read in the partition,
+    /// [None] at its start
+    last: Option<(Tattoo, Weight)>,
+}
+
+/// Convert the error of a `Query` of the given index, reporting a missing index as
+/// a [sheep_shed::errors::Error::IndexNotAvailable]
+fn index_query_error(
+    index_name: &str,
+    e: SdkError<QueryError, HttpResponse>,
+) -> sheep_shed::errors::Error {
+    match e.as_service_error() {
+        // DynamoDB reports a missing index as a ValidationException,
+        // some local emulators as a ResourceNotFoundException
+        Some(qe)
+            if matches!(
+                qe.code(),
+                Some("ValidationException" | "ResourceNotFoundException")
+            ) && qe.message().is_some_and(|m| m.contains(index_name)) =>
+        {
+            let err_string = format!("{qe} ({:?}: {:?})", qe.code(), qe.message());
+            log::error!("{err_string}");
+            sheep_shed::errors::Error::IndexNotAvailable(err_string)
+        }
+        _ => database_error(e),
+    }
+}
+
+/// The condition of a write on the item of a living [Sheep] that must still be at
+/// the given `version`, using the `#key` and `#version` names and the `:version` value
+fn version_condition(version: u64) -> String {
+    if version == 0 {
+        format!(
+            "attribute_exists(#key) AND attribute_not_exists({KILLED_AT_ATTRIBUTE}) \
+            AND (attribute_not_exists(#version) OR #version = :version)"
+        )
+    } else {
+        format!(
+            "attribute_exists(#key) AND attribute_not_exists({KILLED_AT_ATTRIBUTE}) \
+            AND #version = :version"
+        )
+    }
+}
+
+/// Decode an item read from the table, reporting an item that does not decode
+/// (e.g. a legacy or hand-written one) as a [sheep_shed::errors::Error::GenericError]
+/// instead of panicking
+fn decode_item<T: serde::de::DeserializeOwned>(
+    item: HashMap<String, AttributeValue>,
+) -> Result<T, sheep_shed::errors::Error> {
+    from_item(item).map_err(|e| {
+        let err_string = format!("cannot decode an item of the table: {e}");
+        log::error!("{err_string}");
+        sheep_shed::errors::Error::GenericError(err_string)
+    })
+}
+
+/// Decode the items of a page of a scan, skipping (and logging) the ones that
+/// do not decode so a single invalid item does not fail the whole read
+fn decode_valid_items<T: serde::de::DeserializeOwned>(
+    items: Vec<HashMap<String, AttributeValue>>,
+) -> impl Iterator<Item = T> {
+    items.into_iter().filter_map(|item| decode_item(item).ok())
+}
+
+/// Return `true` if the item is the tombstone of a killed [Sheep]
+fn is_tombstone(item: &HashMap<String, AttributeValue>) -> bool {
+    item.contains_key(KILLED_AT_ATTRIBUTE)
+}
+
+/// The error of a write on the item of a [Sheep] whose condition failed, given
+/// the item as it was when the condition was checked, if any
+fn condition_failure(
+    tattoo: &Tattoo,
+    item: Option<&HashMap<String, AttributeValue>>,
+) -> sheep_shed::errors::Error {
+    match item {
+        Some(item) if !is_tombstone(item) => {
+            sheep_shed::errors::Error::ConcurrentModification(tattoo.clone())
+        }
+        _ => sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()),
+    }
+}
 
 /// A [SheepShed] that rely on a DynamoDB database
-/// # Important note
-/// It is expected that it is always use in the context of
-/// a [tokio::runtime::Runtime] of the multi_thread kind as this will heavily
-/// rely on calling [tokio::runtime::Handle::current].
 ///
-/// Also, the calls to the method of the [SheepShed] trait MUST always
-/// be called with [tokio::task::spawn_blocking].
+/// Several sheds can share the same table, each [DynamoDBSheepShed] only sees
+/// the [Sheep]s of its own [ShedId] (see [SORT_KEY_ATTRIBUTE]).
+/// # Important note
+/// The native implementation is the [AsyncSheepShed] one and it is the one
+/// that should be used from an async context (e.g. a Lambda function).
 ///
-/// Note that it is of course MUCH easier to just stay in the async context,
-/// and for this pet project we could easily keep it all "async" if the
-/// SheepShed trait was async. That being said, in real life Traits from
-/// external crates are often defined as "sync" so making this
-/// async -> sync -> async bridge is unfortunately difficult to avoid at
-/// some point (and async Trait are only like a month old anyway).
+/// The [SheepShed] implementation is kept for sync callers and bridges
+/// async -> sync -> async: it expects to be used in the context of
+/// a [tokio::runtime::Runtime] of the multi_thread kind as this will heavily
+/// rely on calling [tokio::runtime::Handle::current], and its methods
+/// MUST always be called with [tokio::task::spawn_blocking].
 #[derive(Debug)]
 pub struct DynamoDBSheepShed {
     client: Client,
     table_name: String,
+    shed_id: ShedId,
+    key_attribute: String,
+    cursor_key: Option<Vec<u8>>,
+    consistent_read: bool,
+    tombstone_retention: Option<Duration>,
+    killer: Option<String>,
+    sheep_count_mode: SheepCountMode,
+    scan_config: ScanConfig,
+    retry_config: RetryConfig,
 }
 
 impl DynamoDBSheepShed {
-    /// Creates a new [DynamoDBSheepShed] from a [Client] and a `table_name`
-    /// # Panics
-    /// Panics if called outside of a [tokio] context.
-    pub fn new(client: Client) -> Self {
-        let table_name = std::env::var("BACKEND_TABLE_NAME")
-            .expect("Mandatory environment variable `BACKEND_TABLE_NAME` is not set");
-        log::info!("BACKEND_TABLE_NAME={table_name}");
-        DynamoDBSheepShed::local_new(client, table_name)
+    /// Creates a new [DynamoDBSheepShed] for the shed with the given [ShedId], from
+    /// a [Client] and the environment variables, see [DynamoDBSheepShedBuilder::from_env].
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the
+    /// environment variables are missing or invalid.
+    pub fn new(client: Client, shed_id: ShedId) -> Result<Self, sheep_shed::errors::Error> {
+        log::info!("shed_id={shed_id}");
+        DynamoDBSheepShedBuilder::from_env()?
+            .with_client(client)
+            .with_shed_id(shed_id)
+            .build()
+    }
+
+    /// Creates a [DynamoDBSheepShedBuilder]
+    pub fn builder() -> DynamoDBSheepShedBuilder {
+        DynamoDBSheepShedBuilder::new()
+    }
+
+    #[cfg(test)]
+    fn local_new(client: Client, table_name: String, shed_id: ShedId) -> Self {
+        Self::builder()
+            .with_client(client)
+            .with_table_name(table_name)
+            .with_shed_id(shed_id)
+            .with_cursor_key("a cursor key for the tests only!")
+            .build()
+            .expect("client, table name and shed id are set")
+    }
+
+    /// The [ShedId] of the shed
+    pub fn shed_id(&self) -> &ShedId {
+        &self.shed_id
+    }
+
+    /// The [SheepTable] of the shed, shared with the other sheds of the table
+    pub fn table(&self) -> SheepTable {
+        SheepTable::new(self.client.clone(), self.table_name.as_str())
+            .with_key_attribute(self.key_attribute.as_str())
+            .with_retry_config(self.retry_config.clone())
+    }
+
+    /// Use the given key to sign the [Cursor]s returned by [AsyncSheepShed::list_page]
+    /// and [DynamoDBSheepShed::list_killed], which refuse to list the shed without one. It must be a secret: anyone who
+    /// knows it can forge a cursor.
+    pub fn with_cursor_key(mut self, cursor_key: impl Into<Vec<u8>>) -> Self {
+        self.cursor_key = Some(cursor_key.into());
+        self
+    }
+
+    /// Use strongly consistent reads in [AsyncSheepShed::get_sheep], the other
+    /// reads that need it always are, except the ones of the [WEIGHT_INDEX_NAME]
+    /// (e.g. [AsyncSheepShed::list_page]) that cannot be
+    pub fn with_consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+
+    /// Switch to tombstone mode: instead of being deleted, a killed [Sheep] is
+    /// marked with the time it was killed and its killer, and its items expire
+    /// after the `retention` (see [EXPIRE_AT_ATTRIBUTE]).
+    ///
+    /// Whatever the mode, the tombstones are invisible to the [AsyncSheepShed]
+    /// methods, use [DynamoDBSheepShed::list_killed] to see them. A [Tattoo] cannot
+    /// be given to a new [Sheep] until its tombstone is purged.
+    pub fn with_tombstones(mut self, retention: Duration) -> Self {
+        self.tombstone_retention = Some(retention);
+        self
+    }
+
+    /// Choose how [AsyncSheepShed::sheep_count] counts the [Sheep]s, see [SheepCountMode].
+    ///
+    /// Whatever the mode, the counter of the shed is maintained by all its writes.
+    pub fn with_sheep_count_mode(mut self, sheep_count_mode: SheepCountMode) -> Self {
+        self.sheep_count_mode = sheep_count_mode;
+        self
+    }
+
+    /// Tune the parallel scans of the whole table, see [ScanConfig]
+    pub fn with_scan_config(mut self, scan_config: ScanConfig) -> Self {
+        self.scan_config = scan_config;
+        self
+    }
+
+    /// Choose how the DynamoDB calls are retried, see [RetryConfig]
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Record the given `killer` in the tombstones of the [Sheep]s killed by this shed
+    pub fn with_killer(mut self, killer: impl Into<String>) -> Self {
+        self.killer = Some(killer.into());
+        self
+    }
+
+    /// The DynamoDB key of the item of the [Sheep] with the given [Tattoo]
+    fn sheep_key(&self, tattoo: &Tattoo) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            (
+                self.key_attribute.clone(),
+                to_attribute_value(tattoo).expect("cannot fail"),
+            ),
+            (SORT_KEY_ATTRIBUTE.to_owned(), sheep_sort_key(&self.shed_id)),
+        ])
     }
 
-    fn local_new(client: Client, table_name: String) -> Self {
-        Self { client, table_name }
+    /// The DynamoDB item of a [Sheep], including its key and the
+    /// [SHED_PRIME_WEIGHT_ATTRIBUTE]
+    fn sheep_item(&self, sheep: &Sheep) -> HashMap<String, AttributeValue> {
+        let mut item: HashMap<String, AttributeValue> = to_item(sheep).expect("cannot fail");
+        item.extend(self.sheep_key(&sheep.tattoo));
+        item.insert(
+            SHED_PRIME_WEIGHT_ATTRIBUTE.to_owned(),
+            shed_prime_weight_value(&self.shed_id, &sheep.weight),
+        );
+        item
     }
 
-    async fn _full_table_scan(
+    /// The DynamoDB item of a [WeighIn] of the [Sheep] with the given [Tattoo]
+    ///
+    /// The timestamp of the [SORT_KEY_ATTRIBUTE] always has nanoseconds so the items
+    /// sort chronologically. The item has no [SHED_PRIME_WEIGHT_ATTRIBUTE], so it does
+    /// not appear in the [WEIGHT_INDEX_NAME] index.
+    fn weigh_in_item(
         &self,
-        count_only: bool,
-    ) -> Result<(usize, Option<Vec<Sheep>>), sheep_shed::errors::Error> {
-        log::info!("_full_table_scan(count_only={count_only})");
-        // Request the approximate item count that DynamoDB updates sometimes
-        let approx_table_size = self
-            .client
-            .describe_table()
+        tattoo: &Tattoo,
+        weigh_in: &WeighIn,
+    ) -> HashMap<String, AttributeValue> {
+        let mut item: HashMap<String, AttributeValue> = to_item(weigh_in).expect("cannot fail");
+        item.insert(
+            self.key_attribute.clone(),
+            to_attribute_value(tattoo).expect("cannot fail"),
+        );
+        item.insert(
+            SORT_KEY_ATTRIBUTE.to_owned(),
+            AttributeValue::S(format!(
+                "{}{}",
+                weigh_in_sort_key_prefix(&self.shed_id),
+                weigh_in
+                    .weighed_at
+                    .to_rfc3339_opts(SecondsFormat::Nanos, true)
+            )),
+        );
+        item
+    }
+
+    /// The [TransactWriteItem] putting the [WeighIn] of a [Sheep]
+    fn put_weigh_in(&self, tattoo: &Tattoo, weigh_in: &WeighIn) -> TransactWriteItem {
+        let put = Put::builder()
+            .table_name(self.table_name.as_str())
+            .set_item(Some(self.weigh_in_item(tattoo, weigh_in)))
+            .build()
+            .expect("table_name and item are set");
+        TransactWriteItem::builder().put(put).build()
+    }
+
+    /// The [TransactWriteItem] putting a new [Sheep] at its first `version`,
+    /// failing if it already exists
+    fn put_new_sheep(&self, sheep: &Sheep) -> TransactWriteItem {
+        let put = Put::builder()
+            .table_name(self.table_name.as_str())
+            .set_item(Some(self.sheep_item(&Sheep {
+                version: 0,
+                ..sheep.clone()
+            })))
+            .condition_expression("attribute_not_exists(#key)")
+            .expression_attribute_names("#key", self.key_attribute.as_str())
+            .build()
+            .expect("table_name and item are set");
+        TransactWriteItem::builder().put(put).build()
+    }
+
+    /// The DynamoDB key of the counter item of the shed
+    fn sheep_counter_key(&self) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            (
+                self.key_attribute.clone(),
+                to_attribute_value(SHEEP_COUNTER_TATTOO).expect("cannot fail"),
+            ),
+            (
+                SORT_KEY_ATTRIBUTE.to_owned(),
+                AttributeValue::S(format!("{}#{SHEEP_COUNTER_SORT_KEY}", self.shed_id)),
+            ),
+        ])
+    }
+
+    /// The [TransactWriteItem] adding `delta` to the counter of the shed,
+    /// creating it if needed
+    fn update_sheep_counter(&self, delta: i64) -> TransactWriteItem {
+        let update = Update::builder()
             .table_name(self.table_name.as_str())
-            .send()
+            .set_key(Some(self.sheep_counter_key()))
+            .update_expression(format!("ADD {SHEEP_COUNT_ATTRIBUTE} :delta"))
+            .expression_attribute_values(":delta", AttributeValue::N(delta.to_string()))
+            .build()
+            .expect("table_name, key and update_expression are set");
+        TransactWriteItem::builder().update(update).build()
+    }
+
+    /// The key signing the [Cursor]s of the shed, see [DynamoDBSheepShed::with_cursor_key]
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if there is none.
+    fn signing_cursor_key(&self) -> Result<&[u8], sheep_shed::errors::Error> {
+        self.cursor_key.as_deref().ok_or_else(|| {
+            let err_string = "no cursor key to sign the cursors, see CURSOR_SIGNING_KEY";
+            log::error!("{err_string}");
+            sheep_shed::errors::Error::InvalidConfiguration(err_string.to_owned())
+        })
+    }
+
+    /// Count the living [Sheep]s of the shed with a parallel scan of the whole table
+    pub(crate) async fn _scan_sheep_count(&self) -> Result<usize, sheep_shed::errors::Error> {
+        self._full_table_scan(true)
+            .await?
+            .try_fold(
+                0,
+                |count, page| async move { Ok(count + page.count as usize) },
+            )
             .await
-            .map_err(|e| {
-                let dte = e.into_service_error();
-                let err_string = format!("{dte} ({:?}: {:?})", dte.code(), dte.message());
-                log::error!("{err_string}");
-                sheep_shed::errors::Error::GenericError(err_string)
-            })?
-            .table
-            .expect("table must exist")
-            .item_count
-            .unwrap_or_default();
+    }
 
-        log::info!("approx_table_size={approx_table_size}");
+    /// Retrieve the living [Sheep] with the given [Tattoo] with a consistent read
+    async fn _get_living_sheep(
+        &self,
+        tattoo: &Tattoo,
+    ) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        let get_item = self
+            .client
+            .get_item()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_key(tattoo)))
+            .consistent_read(true);
+        self.retry_config
+            .call("get_item", || get_item.clone().send())
+            .await
+            .map_err(database_error)?
+            .item
+            .filter(|i| !is_tombstone(i))
+            .map(decode_item::<Sheep>)
+            .transpose()
+    }
 
-        // The database representation of a Sheep is ~50 bytes
-        // Therefore, a scan page of 1MB will contain ~20 000 sheeps
-        // We will be very conservative and devide the table into 100k sheeps segments because
-        // it would be very counter-productive to have more parallel scans than we would have had pages.
-        // Bottom line, with 100k items per segment we expect each segment to be fully scanned in 5 requests.
-        let parallel_scan_threads = min(1 + approx_table_size / 100_000, 1_000_000) as i32;
+    /// Write the item of a [Sheep] and add `delta` to the counter of the shed in
+    /// the same transaction.
+    ///
+    /// If the condition of the write fails, the error is given by `condition_failure`
+    /// from the item as it was when the condition was checked, if any.
+    pub(crate) async fn _write_with_counter(
+        &self,
+        write: TransactWriteItem,
+        delta: i64,
+        condition_failure: impl FnOnce(
+            Option<&HashMap<String, AttributeValue>>,
+        ) -> sheep_shed::errors::Error,
+    ) -> Result<(), sheep_shed::errors::Error> {
+        let transact_write_items = self
+            .client
+            .transact_write_items()
+            .transact_items(write)
+            .transact_items(self.update_sheep_counter(delta));
+        let _ = self
+            .retry_config
+            .transact_write_items(transact_write_items)
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(TransactWriteItemsError::TransactionCanceledException(tce))
+                    if tce.cancellation_reasons().first().and_then(|r| r.code())
+                        == Some("ConditionalCheckFailed") =>
+                {
+                    condition_failure(tce.cancellation_reasons()[0].item.as_ref())
+                }
+                _ => database_error(e),
+            })?;
+        Ok(())
+    }
 
-        let handle = tokio::runtime::Handle::current();
-        let futures = (0..parallel_scan_threads)
-            .into_iter()
-            .map(|seg| {
-                let client = self.client.clone();
-                let table_name = self.table_name.clone();
-                handle.spawn(async move {
-                    let mut items = if !count_only { Some(vec![]) } else { None };
-                    let mut count = 0;
-                    let mut exclusive_start_key = None;
-                    loop {
-                        let result = client
-                            .scan()
-                            .table_name(&table_name)
-                            .segment(seg)
-                            .total_segments(parallel_scan_threads)
-                            .set_exclusive_start_key(exclusive_start_key)
-                            .select(if count_only {
-                                Select::Count
-                            } else {
-                                Select::AllAttributes
-                            })
-                            .send()
-                            .await
-                            .map_err(|e| {
-                                let se = e.into_service_error();
-                                let err_string =
-                                    format!("{se} ({:?}: {:?})", se.code(), se.message());
-                                log::error!("{err_string}");
-                                sheep_shed::errors::Error::GenericError(err_string)
-                            })?;
-                        exclusive_start_key = result.last_evaluated_key;
-                        count += result.count as usize;
-                        if !count_only {
-                            items.as_mut().unwrap().extend(
-                                result.items.unwrap_or_default().into_iter().map(|i| {
-                                    from_item(i).expect("cannot fail unless database corrupt")
-                                }),
-                            );
-                        }
-                        if exclusive_start_key.is_none() {
-                            break;
-                        }
-                    }
-                    Ok((count, items))
-                })
-            })
-            .collect::<Vec<_>>();
+    /// Kill the [Sheep] with the given [Tattoo], if it is still at the
+    /// `expected_version` when there is one.
+    ///
+    /// The [Sheep] is read first, then killed on the condition that it is still at
+    /// the `version` it was read with, in the same transaction as the decrement of
+    /// the counter. Without an `expected_version`, the kill is retried up to
+    /// [TRANSACT_WRITE_MAX_ATTEMPTS] times if the [Sheep] is modified in the meantime.
+    ///
+    /// The [Sheep] and its [WeighIn]s are deleted, unless the shed is in tombstone
+    /// mode.
+    async fn _kill_sheep(
+        &self,
+        tattoo: &Tattoo,
+        expected_version: Option<u64>,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        let mut attempt = 0;
+        loop {
+            let sheep = self
+                ._get_living_sheep(tattoo)
+                .await?
+                .ok_or_else(|| sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()))?;
+            if expected_version.is_some_and(|version| version != sheep.version) {
+                return Err(sheep_shed::errors::Error::ConcurrentModification(
+                    tattoo.clone(),
+                ));
+            }
+            let result = match self.tombstone_retention {
+                Some(retention) => self._tombstone_sheep(&sheep, retention).await,
+                None => self._delete_sheep(&sheep).await,
+            };
+            match result {
+                Err(sheep_shed::errors::Error::ConcurrentModification(_))
+                    if expected_version.is_none() && attempt + 1 < TRANSACT_WRITE_MAX_ATTEMPTS =>
+                {
+                    attempt += 1;
+                    log::warn!("{tattoo} was modified before it could be killed, retrying");
+                    // Randomized exponential backoff before retrying
+                    tokio::time::sleep(self.retry_config.delay(attempt - 1)).await;
+                }
+                result => return result,
+            }
+        }
+    }
 
-        log::info!("Launched {} green-threads", futures.len());
+    /// Delete the item of the [Sheep], if it is still at its `version`, then its
+    /// [WeighIn]s.
+    async fn _delete_sheep(&self, sheep: &Sheep) -> Result<Sheep, sheep_shed::errors::Error> {
+        let tattoo = &sheep.tattoo;
+        let delete = Delete::builder()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_key(tattoo)))
+            .condition_expression(version_condition(sheep.version))
+            .expression_attribute_names("#key", self.key_attribute.as_str())
+            .expression_attribute_names("#version", VERSION_ATTRIBUTE)
+            .expression_attribute_values(":version", AttributeValue::N(sheep.version.to_string()))
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .build()
+            .expect("table_name and key are set");
+        self._write_with_counter(
+            TransactWriteItem::builder().delete(delete).build(),
+            -1,
+            |item| condition_failure(tattoo, item),
+        )
+        .await?;
+        self._delete_weight_history(tattoo).await?;
+        log::info!("_delete_sheep => Ok({sheep})");
+        Ok(sheep.clone())
+    }
 
-        let mut items = if !count_only { Some(vec![]) } else { None };
-        let mut count = 0;
-        for f in futures {
-            let (sub_count, sub_vec) = f.await.unwrap()?;
-            count += sub_count;
-            if !count_only {
-                items.as_mut().unwrap().extend(sub_vec.unwrap().into_iter());
+    /// Retrieve the items of all the [WeighIn]s of the [Sheep] with the given [Tattoo]
+    async fn _weigh_in_items(
+        &self,
+        tattoo: &Tattoo,
+    ) -> Result<Vec<HashMap<String, AttributeValue>>, sheep_shed::errors::Error> {
+        let mut items = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let query = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .key_condition_expression("#key = :tattoo AND begins_with(#sk, :prefix)")
+                .expression_attribute_names("#key", self.key_attribute.as_str())
+                .expression_attribute_names("#sk", SORT_KEY_ATTRIBUTE)
+                .expression_attribute_values(
+                    ":tattoo",
+                    to_attribute_value(tattoo).expect("cannot fail"),
+                )
+                .expression_attribute_values(
+                    ":prefix",
+                    AttributeValue::S(weigh_in_sort_key_prefix(&self.shed_id)),
+                )
+                .consistent_read(true)
+                .set_exclusive_start_key(exclusive_start_key);
+            let page = self
+                .retry_config
+                .call("query", || query.clone().send())
+                .await
+                .map_err(database_error)?;
+            items.extend(page.items.unwrap_or_default());
+            exclusive_start_key = page.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
             }
         }
-        log::info!(
-            "_full_table_scan => Ok(({count}, {}))",
-            if items.is_some() { "Some" } else { "None" }
-        );
-        Ok((count, items))
+        Ok(items)
     }
 
-    async fn _sheep_iter_impl(
+    /// Send the [WriteRequest]s with `BatchWriteItem`s of at most
+    /// [BATCH_WRITE_MAX_ITEMS] requests, retrying the unprocessed ones.
+    async fn _batch_write(
         &self,
-    ) -> Result<impl Iterator<Item = Sheep>, sheep_shed::errors::Error> {
-        Ok(self._full_table_scan(false).await?.1.unwrap().into_iter())
+        requests: Vec<WriteRequest>,
+    ) -> Result<(), sheep_shed::errors::Error> {
+        for chunk in requests.chunks(BATCH_WRITE_MAX_ITEMS) {
+            let mut requests = chunk.to_vec();
+            let mut attempt = 0;
+            while !requests.is_empty() {
+                let batch_write_item = self
+                    .client
+                    .batch_write_item()
+                    .request_items(self.table_name.as_str(), requests);
+                let mut unprocessed = self
+                    .retry_config
+                    .call("batch_write_item", || batch_write_item.clone().send())
+                    .await
+                    .map_err(database_error)?
+                    .unprocessed_items
+                    .unwrap_or_default();
+                requests = unprocessed.remove(&self.table_name).unwrap_or_default();
+                if !requests.is_empty() {
+                    attempt += 1;
+                    if attempt >= TRANSACT_WRITE_MAX_ATTEMPTS {
+                        // DynamoDB leaves requests unprocessed when it throttles them
+                        let err_string =
+                            format!("{} requests could not be processed", requests.len());
+                        log::error!("{err_string}");
+                        return Err(sheep_shed::errors::Error::Throttled(err_string));
+                    }
+                    // Randomized exponential backoff before retrying
+                    tokio::time::sleep(self.retry_config.delay(attempt - 1)).await;
+                }
+            }
+        }
+        Ok(())
     }
-    async fn _sheep_count_impl(&self) -> Result<usize, sheep_shed::errors::Error> {
-        Ok(self._full_table_scan(true).await?.0)
+
+    /// Delete all the [WeighIn]s of the [Sheep] with the given [Tattoo]
+    async fn _delete_weight_history(
+        &self,
+        tattoo: &Tattoo,
+    ) -> Result<(), sheep_shed::errors::Error> {
+        log::info!("_delete_weight_history(tattoo={tattoo})");
+        let requests = self
+            ._weigh_in_items(tattoo)
+            .await?
+            .into_iter()
+            .map(|mut item| {
+                item.retain(|name, _| *name == self.key_attribute || name == SORT_KEY_ATTRIBUTE);
+                let delete = DeleteRequest::builder()
+                    .set_key(Some(item))
+                    .build()
+                    .expect("key is set");
+                WriteRequest::builder().delete_request(delete).build()
+            })
+            .collect::<Vec<_>>();
+        let count = requests.len();
+        self._batch_write(requests).await?;
+        log::info!("_delete_weight_history => Ok({count} weigh-ins)");
+        Ok(())
     }
-    async fn _add_sheep_impl(&self, sheep: Sheep) -> Result<(), sheep_shed::errors::Error> {
-        log::info!("_add_sheep_impl(sheep={sheep})");
-        let _ = self
-            .client
-            .put_item()
+    /// One attempt of [AsyncSheepShed::transfer_sheep]: read the [Sheep] and its
+    /// [WeighIn]s, then move it to `to` in one transaction on the condition that it
+    /// is still at the `version` it was read with.
+    ///
+    /// Return the [Sheep] as it is in `to` and the [WeighIn]s that did not fit in
+    /// the transaction, the oldest ones.
+    async fn _transfer_sheep(
+        &self,
+        to: &Self,
+        tattoo: &Tattoo,
+    ) -> Result<(Sheep, Vec<WeighIn>), sheep_shed::errors::Error> {
+        let sheep = self
+            ._get_living_sheep(tattoo)
+            .await?
+            .ok_or_else(|| sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()))?;
+        let mut weigh_ins = self
+            ._weigh_in_items(tattoo)
+            .await?
+            .into_iter()
+            .map(decode_item::<WeighIn>)
+            .collect::<Result<Vec<_>, _>>()?;
+        // The Sheep takes two items of the transaction: its deletion and its put,
+        // the counters of both sheds two more, the remaining ones are for its most
+        // recent WeighIns
+        let in_transaction = min(weigh_ins.len(), TRANSACT_WRITE_MAX_ITEMS - 4);
+        let recent_weigh_ins = weigh_ins.split_off(weigh_ins.len() - in_transaction);
+
+        let delete = Delete::builder()
             .table_name(self.table_name.as_str())
-            .set_item(Some(to_item(&sheep).expect("cannot fail")))
-            .condition_expression("attribute_not_exists(tattoo)")
-            .send()
+            .set_key(Some(self.sheep_key(tattoo)))
+            .condition_expression(version_condition(sheep.version))
+            .expression_attribute_names("#key", self.key_attribute.as_str())
+            .expression_attribute_names("#version", VERSION_ATTRIBUTE)
+            .expression_attribute_values(":version", AttributeValue::N(sheep.version.to_string()))
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .build()
+            .expect("table_name and key are set");
+        let transact_items = [
+            TransactWriteItem::builder().delete(delete).build(),
+            to.put_new_sheep(&sheep),
+            self.update_sheep_counter(-1),
+            to.update_sheep_counter(1),
+        ]
+        .into_iter()
+        .chain(
+            recent_weigh_ins
+                .iter()
+                .map(|weigh_in| to.put_weigh_in(tattoo, weigh_in)),
+        )
+        .collect();
+        let transact_write_items = self
+            .client
+            .transact_write_items()
+            .set_transact_items(Some(transact_items));
+        let _ = self
+            .retry_config
+            .transact_write_items(transact_write_items)
             .await
             .map_err(|e| {
-                let pie = e.into_service_error();
-                match pie {
-                    PutItemError::ConditionalCheckFailedException(_) => {
-                        sheep_shed::errors::Error::SheepDuplicationError(sheep.tattoo)
+                if let Some(TransactWriteItemsError::TransactionCanceledException(tce)) =
+                    e.as_service_error()
+                {
+                    let reasons = tce.cancellation_reasons();
+                    let failed = |position: usize| {
+                        reasons.get(position).and_then(|r| r.code())
+                            == Some("ConditionalCheckFailed")
+                    };
+                    if failed(0) {
+                        // The Sheep was modified or killed since we read it
+                        return condition_failure(tattoo, reasons[0].item.as_ref());
                     }
-                    _ => {
-                        let err_string = format!("{pie} ({:?}: {:?})", pie.code(), pie.message());
-                        log::error!("{err_string}");
-                        sheep_shed::errors::Error::GenericError(err_string)
+                    if failed(1) {
+                        return sheep_shed::errors::Error::SheepDuplicationError(tattoo.clone());
                     }
                 }
+                database_error(e)
+            })?;
+        Ok((
+            Sheep {
+                version: 0,
+                ..sheep
+            },
+            weigh_ins,
+        ))
+    }
+}
+
+impl AsyncSheepShed for DynamoDBSheepShed {
+    async fn add_sheep(&mut self, sheep: Sheep) -> Result<(), sheep_shed::errors::Error> {
+        log::info!("add_sheep(sheep={sheep})");
+        sheep.validate()?;
+        let transact_write_items = self
+            .client
+            .transact_write_items()
+            .transact_items(self.put_new_sheep(&sheep))
+            .transact_items(self.put_weigh_in(&sheep.tattoo, &WeighIn::now(sheep.weight)))
+            .transact_items(self.update_sheep_counter(1));
+        let _ = self
+            .retry_config
+            .transact_write_items(transact_write_items)
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(TransactWriteItemsError::TransactionCanceledException(tce))
+                    if tce.cancellation_reasons().first().and_then(|r| r.code())
+                        == Some("ConditionalCheckFailed") =>
+                {
+                    sheep_shed::errors::Error::SheepDuplicationError(sheep.tattoo.clone())
+                }
+                _ => database_error(e),
             })?;
-        log::info!("_add_sheep_impl => Ok(())");
+        log::info!("add_sheep => Ok(())");
         Ok(())
     }
-    async fn _kill_sheep_impl(&self, tattoo: &Tattoo) -> Result<Sheep, sheep_shed::errors::Error> {
-        log::info!("_kill_sheep_impl(tattoo={tattoo})");
-        let sheep = self
+
+    /// Add a batch of new [Sheep]s using chunks of at most [TRANSACT_WRITE_MAX_ITEMS]
+    /// writes in a [TransactWriteItems](aws_sdk_dynamodb::Client::transact_write_items):
+    /// a conditional put of each [Sheep] followed by the put of its first [WeighIn],
+    /// and the update of the counter of the shed.
+    ///
+    /// When a transaction is cancelled, the [Sheep]s that failed their condition are
+    /// reported as [AddSheepOutcome::Duplicate] and the others are retried with a new
+    /// transaction. If a transaction makes no progress after [TRANSACT_WRITE_MAX_ATTEMPTS]
+    /// attempts, its remaining [Sheep]s are reported as [AddSheepOutcome::Failed].
+    async fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> Result<Vec<(Tattoo, AddSheepOutcome)>, sheep_shed::errors::Error> {
+        log::info!("add_sheep_batch(sheeps.len()={})", sheeps.len());
+        let mut outcomes = vec![None; sheeps.len()];
+
+        // DynamoDB does not allow multiple operations on the same item in one
+        // transaction, so duplicates inside the batch are resolved beforehand,
+        // as well as invalid sheeps
+        let mut seen = HashSet::new();
+        let mut to_insert = vec![];
+        for (index, sheep) in sheeps.iter().enumerate() {
+            if let Err(e) = sheep.validate() {
+                outcomes[index] = Some(AddSheepOutcome::Failed {
+                    reason: e.to_string(),
+                });
+            } else if seen.insert(&sheep.tattoo) {
+                to_insert.push(index);
+            } else {
+                outcomes[index] = Some(AddSheepOutcome::Duplicate);
+            }
+        }
+
+        // Each Sheep takes two items of the transaction: itself and its first WeighIn,
+        // and the counter takes the last one
+        for chunk in to_insert.chunks((TRANSACT_WRITE_MAX_ITEMS - 1) / 2) {
+            let mut pending = chunk.to_vec();
+            let mut attempt = 0;
+            while !pending.is_empty() {
+                let transact_items = pending
+                    .iter()
+                    .flat_map(|&index| {
+                        let sheep = &sheeps[index];
+                        [
+                            self.put_new_sheep(sheep),
+                            self.put_weigh_in(&sheep.tattoo, &WeighIn::now(sheep.weight)),
+                        ]
+                    })
+                    .chain([self.update_sheep_counter(pending.len() as i64)])
+                    .collect();
+                let transact_write_items = self
+                    .client
+                    .transact_write_items()
+                    .set_transact_items(Some(transact_items));
+                let result = self
+                    .retry_config
+                    .transact_write_items(transact_write_items)
+                    .await;
+                match result {
+                    Ok(_) => {
+                        for index in pending.drain(..) {
+                            outcomes[index] = Some(AddSheepOutcome::Inserted);
+                        }
+                    }
+                    Err(e) => match e.as_service_error() {
+                        Some(TransactWriteItemsError::TransactionCanceledException(tce)) => {
+                            let reasons = tce.cancellation_reasons();
+                            let mut retry = vec![];
+                            for (position, &index) in pending.iter().enumerate() {
+                                match reasons.get(2 * position).and_then(|r| r.code()) {
+                                    Some("ConditionalCheckFailed") => {
+                                        outcomes[index] = Some(AddSheepOutcome::Duplicate)
+                                    }
+                                    _ => retry.push(index),
+                                }
+                            }
+                            if retry.len() == pending.len() {
+                                attempt += 1;
+                                if attempt >= TRANSACT_WRITE_MAX_ATTEMPTS {
+                                    let reason = format!("transaction cancelled: {tce}");
+                                    log::error!("{reason}");
+                                    for index in retry.drain(..) {
+                                        outcomes[index] = Some(AddSheepOutcome::Failed {
+                                            reason: reason.clone(),
+                                        });
+                                    }
+                                } else {
+                                    // Randomized exponential backoff before retrying
+                                    tokio::time::sleep(self.retry_config.delay(attempt - 1)).await;
+                                }
+                            }
+                            pending = retry;
+                        }
+                        _ => {
+                            let reason = database_error(e).to_string();
+                            for index in pending.drain(..) {
+                                outcomes[index] = Some(AddSheepOutcome::Failed {
+                                    reason: reason.clone(),
+                                });
+                            }
+                        }
+                    },
+                }
+            }
+        }
+
+        let outcomes = sheeps
+            .into_iter()
+            .zip(outcomes)
+            .map(|(sheep, outcome)| (sheep.tattoo, outcome.expect("all sheeps are processed")))
+            .collect::<Vec<_>>();
+        log::info!("add_sheep_batch => Ok(outcomes.len()={})", outcomes.len());
+        Ok(outcomes)
+    }
+
+    /// Depending on the [SheepCountMode], read the counter of the shed or scan
+    /// the whole table.
+    async fn sheep_count(&self) -> Result<usize, sheep_shed::errors::Error> {
+        let count = match self.sheep_count_mode {
+            SheepCountMode::Scan => self._scan_sheep_count().await?,
+            SheepCountMode::Counter => self._read_sheep_counter().await?.max(0) as usize,
+        };
+        log::info!("sheep_count => Ok({count})");
+        Ok(count)
+    }
+
+    async fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        log::info!("get_sheep(tattoo={tattoo})");
+        let get_item = self
             .client
-            .delete_item()
+            .get_item()
             .table_name(self.table_name.as_str())
-            .key("tattoo", to_attribute_value(tattoo).expect("cannot fail"))
-            .condition_expression("attribute_exists(tattoo)")
-            .return_values(ReturnValue::AllOld)
-            .send()
+            .set_key(Some(self.sheep_key(tattoo)))
+            .consistent_read(self.consistent_read);
+        let sheep = self
+            .retry_config
+            .call("get_item", || get_item.clone().send())
             .await
-            .map_err(|e| {
-                let die = e.into_service_error();
-                match die {
-                    DeleteItemError::ConditionalCheckFailedException(_) => {
-                        sheep_shed::errors::Error::SheepNotPresent(tattoo.clone())
-                    }
-                    _ => {
-                        let err_string = format!("{die} ({:?}: {:?})", die.code(), die.message());
-                        log::error!("{err_string}");
-                        sheep_shed::errors::Error::GenericError(err_string)
+            .map_err(database_error)?
+            .item
+            .filter(|i| !is_tombstone(i))
+            .map(decode_item::<Sheep>)
+            .transpose()?;
+        log::info!("get_sheep => Ok({sheep:?})");
+        Ok(sheep)
+    }
+
+    async fn sheep_iter(
+        &self,
+    ) -> Result<impl Iterator<Item = Sheep> + Send, sheep_shed::errors::Error> {
+        Ok(self
+            .sheep_stream()
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter())
+    }
+
+    /// The [Sheep]s are found by `Query`s of the two [SHED_PRIME_WEIGHT_ATTRIBUTE]
+    /// partitions of the shed in the [WEIGHT_INDEX_NAME], the composite weights
+    /// then the prime ones, each in ascending `weight` order: only the [Sheep]s of
+    /// the shed are read, whatever the size of the table.
+    ///
+    /// The [Cursor] holds the partition, the [Tattoo] and the [Weight] of the last
+    /// [Sheep] read, the rest of its key is the one of the shed. It is signed with
+    /// the cursor key of the [DynamoDBSheepShed] and the [ShedId], so another shed
+    /// refuses it.
+    ///
+    /// As the index is eventually consistent, a [Sheep] just added may be missing,
+    /// and a [Sheep] weighed between two pages may be missed or listed twice.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the
+    /// [DynamoDBSheepShed] has no cursor key (see [DynamoDBSheepShed::with_cursor_key]),
+    /// or an [sheep_shed::errors::Error::IndexNotAvailable] if the table does not
+    /// have the [WEIGHT_INDEX_NAME] index.
+    async fn list_page(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<SheepPage, sheep_shed::errors::Error> {
+        log::info!("list_page(cursor={cursor:?}, limit={limit})");
+        let cursor_key = self.signing_cursor_key()?;
+        let scope = self.shed_id.to_string();
+        let mut position = cursor
+            .map(|c| {
+                let payload = c.open_scoped(scope.as_bytes(), cursor_key)?;
+                serde_json::from_slice::<PagePosition>(&payload)
+                    .map_err(|_| sheep_shed::errors::Error::InvalidCursor(c.to_string()))
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let mut sheeps = vec![];
+        let next_position = loop {
+            if sheeps.len() == limit {
+                break Some(position);
+            }
+            let shed_prime_weight = AttributeValue::S(format!(
+                "{}#{}",
+                self.shed_id,
+                if position.prime { "1" } else { "0" }
+            ));
+            let exclusive_start_key = position.last.as_ref().map(|(tattoo, weight)| {
+                HashMap::from([
+                    (
+                        self.key_attribute.clone(),
+                        to_attribute_value(tattoo).expect("cannot fail"),
+                    ),
+                    (SORT_KEY_ATTRIBUTE.to_owned(), sheep_sort_key(&self.shed_id)),
+                    (
+                        SHED_PRIME_WEIGHT_ATTRIBUTE.to_owned(),
+                        shed_prime_weight.clone(),
+                    ),
+                    (
+                        "weight".to_owned(),
+                        to_attribute_value(weight).expect("cannot fail"),
+                    ),
+                ])
+            });
+            let query = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .index_name(WEIGHT_INDEX_NAME)
+                .key_condition_expression("#shed_prime_weight = :shed_prime_weight")
+                .expression_attribute_names("#shed_prime_weight", SHED_PRIME_WEIGHT_ATTRIBUTE)
+                .expression_attribute_values(":shed_prime_weight", shed_prime_weight)
+                .set_exclusive_start_key(exclusive_start_key)
+                .limit((limit - sheeps.len()).try_into().unwrap_or(i32::MAX));
+            let result = self
+                .retry_config
+                .call("query", || query.clone().send())
+                .await
+                .map_err(|e| index_query_error(WEIGHT_INDEX_NAME, e))?;
+            sheeps.extend(decode_valid_items::<Sheep>(
+                result.items.unwrap_or_default(),
+            ));
+            position = match result.last_evaluated_key {
+                // The last evaluated key is always a Sheep of the partition
+                Some(key) => PagePosition {
+                    last: Some(
+                        key.get(self.key_attribute.as_str())
+                            .zip(key.get("weight"))
+                            .and_then(|(tattoo, weight)| {
+                                Some((
+                                    from_attribute_value(tattoo.clone()).ok()?,
+                                    from_attribute_value(weight.clone()).ok()?,
+                                ))
+                            })
+                            .ok_or_else(|| {
+                                let err_string = format!("unexpected last evaluated key {key:?}");
+                                log::error!("{err_string}");
+                                sheep_shed::errors::Error::GenericError(err_string)
+                            })?,
+                    ),
+                    ..position
+                },
+                None if !position.prime => PagePosition {
+                    prime: true,
+                    last: None,
+                },
+                None => break None,
+            };
+        };
+        let next_cursor = next_position.map(|position| {
+            Cursor::seal_scoped(
+                &serde_json::to_vec(&position).expect("cannot fail"),
+                scope.as_bytes(),
+                cursor_key,
+            )
+        });
+        log::info!(
+            "list_page => Ok(sheeps.len()={}, next_cursor={next_cursor:?})",
+            sheeps.len()
+        );
+        Ok(SheepPage {
+            sheeps,
+            next_cursor,
+        })
+    }
+
+    /// Query the [WEIGHT_INDEX_NAME] in descending `weight` order, once per
+    /// [SHED_PRIME_WEIGHT_ATTRIBUTE] partition of the shed matching the
+    /// [WeightCriteria], so at most two items are read whatever the size of the table.
+    /// # Errors
+    /// Returns an [sheep_shed::errors::Error::IndexNotAvailable] if the table
+    /// does not have the [WEIGHT_INDEX_NAME] index.
+    async fn heaviest_matching(
+        &self,
+        criteria: WeightCriteria,
+    ) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        log::info!("heaviest_matching(criteria={criteria:?})");
+        let partitions: &[&str] = match criteria {
+            WeightCriteria::Any => &["0", "1"],
+            WeightCriteria::PrimeUg => &["1"],
+        };
+        let shed_id = &self.shed_id;
+        let mut heaviest: Option<Sheep> = None;
+        for &partition in partitions {
+            let query = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .index_name(WEIGHT_INDEX_NAME)
+                .key_condition_expression("#shed_prime_weight = :shed_prime_weight")
+                .expression_attribute_names("#shed_prime_weight", SHED_PRIME_WEIGHT_ATTRIBUTE)
+                .expression_attribute_values(
+                    ":shed_prime_weight",
+                    AttributeValue::S(format!("{shed_id}#{partition}")),
+                )
+                .scan_index_forward(false)
+                .limit(1);
+            let sheep = self
+                .retry_config
+                .call("query", || query.clone().send())
+                .await
+                .map_err(|e| index_query_error(WEIGHT_INDEX_NAME, e))?
+                .items
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(decode_item::<Sheep>)
+                .transpose()?;
+            if let Some(sheep) = sheep {
+                if !heaviest.as_ref().is_some_and(|h| h.weight > sheep.weight) {
+                    heaviest = Some(sheep);
+                }
+            }
+        }
+        log::info!("heaviest_matching => Ok({heaviest:?})");
+        Ok(heaviest)
+    }
+
+    fn sheep_stream(
+        &self,
+    ) -> impl Stream<Item = Result<Sheep, sheep_shed::errors::Error>> + Send + '_ {
+        stream::once(self._full_table_scan(false))
+            .try_flatten()
+            .map_ok(|page| stream::iter(decode_valid_items(page.items.unwrap_or_default()).map(Ok)))
+            .try_flatten()
+    }
+
+    /// Delete the item of the [Sheep], then the items of its [WeighIn]s with
+    /// `BatchWriteItem`s of at most [BATCH_WRITE_MAX_ITEMS] deletions.
+    /// # Errors
+    /// If the [WeighIn]s cannot be deleted, the [Sheep] is nonetheless dead and
+    /// a [sheep_shed::errors::Error::GenericError] is returned.
+    async fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, sheep_shed::errors::Error> {
+        log::info!("kill_sheep(tattoo={tattoo})");
+        self._kill_sheep(tattoo, None).await
+    }
+
+    /// Same as [AsyncSheepShed::kill_sheep], with the deletion conditioned on the
+    /// `version` of the [Sheep].
+    async fn kill_sheep_if_unchanged(
+        &mut self,
+        sheep: &Sheep,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        log::info!(
+            "kill_sheep_if_unchanged(sheep={sheep}, version={})",
+            sheep.version
+        );
+        self._kill_sheep(&sheep.tattoo, Some(sheep.version)).await
+    }
+
+    /// Read the [Sheep] as it is before the weigh-in, then update its [Weight]
+    /// and put the new [WeighIn] in the same transaction, on the condition that
+    /// the [Sheep] is still at the `version` it was read with.
+    async fn update_weight(
+        &mut self,
+        tattoo: &Tattoo,
+        weight: Weight,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        log::info!("update_weight(tattoo={tattoo}, weight={weight})");
+        weight.validate()?;
+        let sheep = self
+            ._get_living_sheep(tattoo)
+            .await?
+            .ok_or_else(|| sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()))?;
+        let update = Update::builder()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_key(tattoo)))
+            .update_expression(format!(
+                "SET weight = :weight, {SHED_PRIME_WEIGHT_ATTRIBUTE} = :shed_prime_weight, \
+                #version = :next_version"
+            ))
+            .expression_attribute_values(
+                ":weight",
+                to_attribute_value(weight).expect("cannot fail"),
+            )
+            .expression_attribute_values(
+                ":shed_prime_weight",
+                shed_prime_weight_value(&self.shed_id, &weight),
+            )
+            .expression_attribute_values(
+                ":next_version",
+                AttributeValue::N((sheep.version + 1).to_string()),
+            )
+            .condition_expression(version_condition(sheep.version))
+            .expression_attribute_names("#key", self.key_attribute.as_str())
+            .expression_attribute_names("#version", VERSION_ATTRIBUTE)
+            .expression_attribute_values(":version", AttributeValue::N(sheep.version.to_string()))
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .build()
+            .expect("table_name, key and update_expression are set");
+        let transact_write_items = self
+            .client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().update(update).build())
+            .transact_items(self.put_weigh_in(tattoo, &WeighIn::now(weight)));
+        let _ = self
+            .retry_config
+            .transact_write_items(transact_write_items)
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(TransactWriteItemsError::TransactionCanceledException(tce))
+                    if tce.cancellation_reasons().first().and_then(|r| r.code())
+                        == Some("ConditionalCheckFailed") =>
+                {
+                    // The Sheep was modified or killed since we read it
+                    condition_failure(tattoo, tce.cancellation_reasons()[0].item.as_ref())
+                }
+                _ => database_error(e),
+            })?;
+        log::info!("update_weight => Ok({sheep})");
+        Ok(sheep)
+    }
+
+    /// Query the items of the shed in the partition of the [Sheep]: its own item
+    /// comes first, followed by its [WeighIn]s in chronological order.
+    async fn weight_history(
+        &self,
+        tattoo: &Tattoo,
+    ) -> Result<Vec<WeighIn>, sheep_shed::errors::Error> {
+        log::info!("weight_history(tattoo={tattoo})");
+        let sheep_sort_key = sheep_sort_key(&self.shed_id);
+        let weigh_in_sort_key_prefix = weigh_in_sort_key_prefix(&self.shed_id);
+        let counter_key = self.sheep_counter_key();
+        let mut sheep_found = false;
+        let mut history = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let query = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .key_condition_expression("#key = :tattoo AND begins_with(#sk, :shed_prefix)")
+                .expression_attribute_names("#key", self.key_attribute.as_str())
+                .expression_attribute_names("#sk", SORT_KEY_ATTRIBUTE)
+                .expression_attribute_values(
+                    ":tattoo",
+                    to_attribute_value(tattoo).expect("cannot fail"),
+                )
+                .expression_attribute_values(
+                    ":shed_prefix",
+                    AttributeValue::S(format!("{}#", self.shed_id)),
+                )
+                .consistent_read(true)
+                .set_exclusive_start_key(exclusive_start_key);
+            let page = self
+                .retry_config
+                .call("query", || query.clone().send())
+                .await
+                .map_err(database_error)?;
+            for item in page.items.unwrap_or_default() {
+                match item.get(SORT_KEY_ATTRIBUTE) {
+                    Some(sk) if *sk == sheep_sort_key => sheep_found = !is_tombstone(&item),
+                    Some(AttributeValue::S(sk)) if sk.starts_with(&weigh_in_sort_key_prefix) => {
+                        history.push(decode_item(item)?)
                     }
+                    // The counter of the shed lives in the partition of the SHEEP_COUNTER_TATTOO
+                    sk if sk == counter_key.get(SORT_KEY_ATTRIBUTE) => {}
+                    _ => log::warn!("unexpected item in the partition of {tattoo}: {item:?}"),
+                }
+            }
+            exclusive_start_key = page.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+        if !sheep_found {
+            return Err(sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()));
+        }
+        log::info!("weight_history => Ok(history.len()={})", history.len());
+        Ok(history)
+    }
+
+    /// Move the [Sheep] with a [TransactWriteItems](aws_sdk_dynamodb::Client::transact_write_items):
+    /// the deletion of its item in this shed, conditioned on its `version`, the
+    /// conditional put of its item in `to`, the updates of the counters of both sheds
+    /// and the puts of its most recent [WeighIn]s in `to`, as many as the transaction
+    /// can hold.
+    ///
+    /// The older [WeighIn]s, if any, are copied to `to` after the transaction, then
+    /// the [WeighIn]s are deleted from this shed like when a [Sheep] is killed. If the
+    /// [Sheep] is modified during the transfer, it is retried up to
+    /// [TRANSACT_WRITE_MAX_ATTEMPTS] times.
+    ///
+    /// Both sheds must share the same table.
+    async fn transfer_sheep(
+        &mut self,
+        to: &mut Self,
+        tattoo: &Tattoo,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        log::info!("transfer_sheep(to={}, tattoo={tattoo})", to.shed_id);
+        if self.table_name != to.table_name {
+            let err_string = format!(
+                "cannot transfer from table {} to table {}",
+                self.table_name, to.table_name
+            );
+            log::error!("{err_string}");
+            return Err(sheep_shed::errors::Error::GenericError(err_string));
+        }
+        if self.shed_id == to.shed_id {
+            // The Sheep is either not there or already at its destination
+            return Err(match AsyncSheepShed::get_sheep(self, tattoo).await? {
+                Some(_) => sheep_shed::errors::Error::SheepDuplicationError(tattoo.clone()),
+                None => sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()),
+            });
+        }
+        let mut attempt = 0;
+        let (sheep, older_weigh_ins) = loop {
+            match self._transfer_sheep(to, tattoo).await {
+                Err(sheep_shed::errors::Error::ConcurrentModification(_))
+                    if attempt + 1 < TRANSACT_WRITE_MAX_ATTEMPTS =>
+                {
+                    attempt += 1;
+                    log::warn!("{tattoo} was modified during its transfer, retrying");
+                    // Randomized exponential backoff before retrying
+                    tokio::time::sleep(self.retry_config.delay(attempt - 1)).await;
                 }
-            })?
-            .attributes
-            .map(|i| from_item(i).expect("cannot fail unless database corrupt"))
-            .expect("DynamoDB verified Sheep was present");
-        log::info!("_kill_sheep_impl => Ok({sheep})");
+                result => break result?,
+            }
+        };
+        let requests = older_weigh_ins
+            .iter()
+            .map(|weigh_in| {
+                let put = PutRequest::builder()
+                    .set_item(Some(to.weigh_in_item(tattoo, weigh_in)))
+                    .build()
+                    .expect("item is set");
+                WriteRequest::builder().put_request(put).build()
+            })
+            .collect::<Vec<_>>();
+        self._batch_write(requests).await?;
+        self._delete_weight_history(tattoo).await?;
+        log::info!("transfer_sheep => Ok({sheep})");
         Ok(sheep)
     }
 }
@@ -219,27 +1371,107 @@ impl SheepShed for DynamoDBSheepShed {
     /// # Panics
     /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
     fn add_sheep(&mut self, sheep: Sheep) -> Result<(), sheep_shed::errors::Error> {
-        tokio::runtime::Handle::current().block_on(self._add_sheep_impl(sheep))
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::add_sheep(self, sheep))
     }
 
-    /// Return the number of [Sheep] in the [SheepShed]
+    /// Add a batch of new [Sheep]s in the [SheepShed]
     /// # Panics
     /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
-    fn sheep_count(&self) -> Result<usize, sheep_shed::errors::Error> {
-        tokio::runtime::Handle::current().block_on(self._sheep_count_impl())
+    fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> Result<Vec<(Tattoo, AddSheepOutcome)>, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::add_sheep_batch(self, sheeps))
+    }
+
+    /// Return the number of [Sheep] in the [SheepShed]
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn sheep_count(&self) -> Result<usize, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::sheep_count(self))
+    }
+    /// Return the [Sheep] with the given [Tattoo], or [None] if there is no such
+    /// [Sheep] in the [SheepShed]
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::get_sheep(self, tattoo))
     }
     /// Return an [Iterator] over references of all the [Sheep]s in the [SheepShed]
     /// # Panics
     /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
     fn sheep_iter(&self) -> Result<impl Iterator<Item = Sheep>, sheep_shed::errors::Error> {
-        tokio::runtime::Handle::current().block_on(self._sheep_iter_impl())
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::sheep_iter(self))
+    }
+
+    /// Return a page of at most `limit` [Sheep]s, starting after the given [Cursor]
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn list_page(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<SheepPage, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::list_page(self, cursor, limit))
+    }
+
+    /// Return the heaviest [Sheep] whose [Weight] matches the [WeightCriteria]
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn heaviest_matching(
+        &self,
+        criteria: WeightCriteria,
+    ) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current()
+            .block_on(AsyncSheepShed::heaviest_matching(self, criteria))
     }
 
     fn kill_sheep(
         &mut self,
         tattoo: &sheep_shed::Tattoo,
     ) -> Result<Sheep, sheep_shed::errors::Error> {
-        tokio::runtime::Handle::current().block_on(self._kill_sheep_impl(tattoo))
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::kill_sheep(self, tattoo))
+    }
+
+    /// Kill the [Sheep] only if it was not modified since it was read
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn kill_sheep_if_unchanged(
+        &mut self,
+        sheep: &Sheep,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current()
+            .block_on(AsyncSheepShed::kill_sheep_if_unchanged(self, sheep))
+    }
+
+    /// Update the [Weight] of a [Sheep] and return it as it was before.
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn update_weight(
+        &mut self,
+        tattoo: &Tattoo,
+        weight: Weight,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current()
+            .block_on(AsyncSheepShed::update_weight(self, tattoo, weight))
+    }
+
+    /// Return the weight history of a [Sheep], oldest [WeighIn] first.
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn weight_history(&self, tattoo: &Tattoo) -> Result<Vec<WeighIn>, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::weight_history(self, tattoo))
+    }
+
+    /// Transfer a [Sheep] to another shed of the same table, with its weight history.
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn transfer_sheep(
+        &mut self,
+        to: &mut Self,
+        tattoo: &Tattoo,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        tokio::runtime::Handle::current().block_on(AsyncSheepShed::transfer_sheep(self, to, tattoo))
     }
 }
 
@@ -282,24 +1514,56 @@ mod tests {
 
     impl TempTable {
         fn new(client: Client, table_name: &str) -> Self {
-            let pkn = "tattoo";
-            let pkad = AttributeDefinition::builder()
-                .attribute_name(pkn)
-                .attribute_type(ScalarAttributeType::N)
-                .build()
-                .unwrap();
-            let pk = KeySchemaElement::builder()
-                .attribute_name(pkn)
-                .key_type(KeyType::Hash)
-                .build()
+            Self::create(client, table_name, DEFAULT_KEY_ATTRIBUTE)
+        }
+
+        fn create(client: Client, table_name: &str, key_attribute: &str) -> Self {
+            tokio::runtime::Handle::current()
+                .block_on(
+                    SheepTable::new(client.clone(), table_name)
+                        .with_key_attribute(key_attribute)
+                        .ensure_table(),
+                )
                 .unwrap();
+
+            Self {
+                client,
+                table_name: table_name.to_owned(),
+            }
+        }
+
+        /// A table whose indexes are missing, as if
+        /// [SheepTable::ensure_table] was never called on it
+        fn without_indexes(client: Client, table_name: &str) -> Self {
+            let attribute_definition = |name, attribute_type| {
+                AttributeDefinition::builder()
+                    .attribute_name(name)
+                    .attribute_type(attribute_type)
+                    .build()
+                    .unwrap()
+            };
+            let key_schema_element = |name, key_type| {
+                KeySchemaElement::builder()
+                    .attribute_name(name)
+                    .key_type(key_type)
+                    .build()
+                    .unwrap()
+            };
             tokio::runtime::Handle::current()
                 .block_on(
                     client
                         .create_table()
                         .table_name(table_name)
-                        .attribute_definitions(pkad)
-                        .key_schema(pk)
+                        .attribute_definitions(attribute_definition(
+                            DEFAULT_KEY_ATTRIBUTE,
+                            ScalarAttributeType::N,
+                        ))
+                        .attribute_definitions(attribute_definition(
+                            SORT_KEY_ATTRIBUTE,
+                            ScalarAttributeType::S,
+                        ))
+                        .key_schema(key_schema_element(DEFAULT_KEY_ATTRIBUTE, KeyType::Hash))
+                        .key_schema(key_schema_element(SORT_KEY_ATTRIBUTE, KeyType::Range))
                         .billing_mode(BillingMode::PayPerRequest)
                         .send(),
                 )
@@ -321,30 +1585,28 @@ mod tests {
                         .table_name(&self.table_name)
                         .send(),
                 )
-                .ok()
-                .or_else(|| None);
+                .ok();
         }
     }
 
+    fn test_shed_id() -> ShedId {
+        "test-shed".parse().unwrap()
+    }
+
     fn prep_base_sheep_shed() -> (TempTable, DynamoDBSheepShed) {
         let client = dynamodb_local_client();
         let table_name = format!("{}", rand::random::<u64>());
         let temp_table = TempTable::new(client.clone(), &table_name);
-        let sheep_shed = DynamoDBSheepShed::local_new(client, table_name);
+        let sheep_shed = DynamoDBSheepShed::local_new(client, table_name, test_shed_id());
         (temp_table, sheep_shed)
     }
 
-    // The DynamoDBSheepShed expects to be called from an async context
-    // In Lambda that make sense because they are launch as async.
-    // So because of the async -> sync -> async expectation of the library,
-    // we simulate that in our tests by launching them "as-if" it was a Lambda
-    // context.
-    // Note that it is of course MUCH easier to just stay in the async context,
-    // and for this pet project we could easily keep it all "async" if the
-    // SheepShed trait was async. That being said, in real life Traits from
-    // external crates are often defined as "sync" so making this
-    // async -> sync -> async bridge is unfortunately difficult to avoid at
-    // some point (and async Trait are only like a month old anyway).
+    // The test templates are sync, so they go through the SheepShed
+    // implementation of the DynamoDBSheepShed, which drives the AsyncSheepShed
+    // one: both flavours are exercised by the same tests.
+    // Because of this async -> sync -> async bridge, we simulate the context
+    // in our tests by launching them "as-if" it was a Lambda context calling
+    // the sync flavour.
     macro_rules! impl_test_template {
         ($tn: tt) => {
             #[test]
@@ -369,4 +1631,1275 @@ mod tests {
     impl_test_template!(sheep_shed_sheep_count);
     impl_test_template!(sheep_shed_iterator);
     impl_test_template!(cannot_kill_inexistent_sheep);
+    impl_test_template!(get_sheep_by_tattoo);
+    impl_test_template!(sheep_weigh_in);
+    impl_test_template!(sheep_batch_insertion);
+    impl_test_template!(sheep_shed_pagination);
+    impl_test_template!(heaviest_matching_sheep);
+    impl_test_template!(invalid_weights_rejected);
+    impl_test_template!(sheep_details_preserved);
+    impl_test_template!(sheep_weight_history);
+    impl_test_template!(optimistic_concurrency);
+
+    // The templates that do not reuse the tattoo of a killed sheep must behave
+    // the same in tombstone mode.
+    mod tombstone_mode {
+        use super::*;
+
+        macro_rules! impl_tombstone_test_template {
+            ($tn: tt) => {
+                #[test]
+                fn $tn() {
+                    let rt = tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()
+                        .unwrap();
+                    rt.block_on(async {
+                        rt.spawn_blocking(|| {
+                            let (_temp, sheep_shed) = prep_base_sheep_shed();
+                            let sheep_shed = sheep_shed.with_tombstones(Duration::from_secs(3600));
+                            sheep_shed::test_templates::$tn(sheep_shed)
+                        })
+                        .await
+                        .unwrap()
+                    })
+                }
+            };
+        }
+
+        impl_tombstone_test_template!(sheep_shed_sheep_count);
+        impl_tombstone_test_template!(cannot_kill_inexistent_sheep);
+        impl_tombstone_test_template!(get_sheep_by_tattoo);
+        impl_tombstone_test_template!(heaviest_matching_sheep);
+        impl_tombstone_test_template!(optimistic_concurrency);
+    }
+
+    // The same templates must pass on a table whose hash key is not `tattoo`
+    mod custom_key_attribute {
+        use super::*;
+
+        macro_rules! impl_custom_key_test_template {
+            ($tn: tt) => {
+                #[test]
+                fn $tn() {
+                    let rt = tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()
+                        .unwrap();
+                    rt.block_on(async {
+                        rt.spawn_blocking(|| {
+                            let client = dynamodb_local_client();
+                            let table_name = format!("{}", rand::random::<u64>());
+                            let _temp = TempTable::create(client.clone(), &table_name, "sheep_id");
+                            let sheep_shed = DynamoDBSheepShed::builder()
+                                .with_client(client)
+                                .with_table_name(table_name)
+                                .with_shed_id(test_shed_id())
+                                .with_key_attribute("sheep_id")
+                                .with_cursor_key("a cursor key for the tests only!")
+                                .with_consistent_read(true)
+                                .with_sheep_count_mode(SheepCountMode::Counter)
+                                .build()
+                                .unwrap();
+                            sheep_shed::test_templates::$tn(sheep_shed)
+                        })
+                        .await
+                        .unwrap()
+                    })
+                }
+            };
+        }
+
+        impl_custom_key_test_template!(sheep_shed_sheep_count);
+        impl_custom_key_test_template!(get_sheep_by_tattoo);
+        impl_custom_key_test_template!(sheep_shed_pagination);
+        impl_custom_key_test_template!(heaviest_matching_sheep);
+        impl_custom_key_test_template!(sheep_weight_history);
+        impl_custom_key_test_template!(optimistic_concurrency);
+
+        #[test]
+        fn sheep_details_preserved_by_projected_scan() {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                rt.spawn_blocking(|| {
+                    let client = dynamodb_local_client();
+                    let table_name = format!("{}", rand::random::<u64>());
+                    let _temp = TempTable::create(client.clone(), &table_name, "sheep_id");
+                    let sheep_shed = DynamoDBSheepShed::builder()
+                        .with_client(client)
+                        .with_table_name(table_name)
+                        .with_shed_id(test_shed_id())
+                        .with_key_attribute("sheep_id")
+                        .with_consistent_read(true)
+                        .build()
+                        .unwrap()
+                        .with_scan_config(
+                            ScanConfig::default()
+                                .with_projection(ScanProjection::Sheep)
+                                .with_consistent_read(true),
+                        );
+                    sheep_shed::test_templates::sheep_details_preserved(sheep_shed)
+                })
+                .await
+                .unwrap()
+            })
+        }
+    }
+
+    #[test]
+    fn builder_reports_missing_parts() {
+        let is_invalid = |result: Result<DynamoDBSheepShed, sheep_shed::errors::Error>| {
+            matches!(
+                result,
+                Err(sheep_shed::errors::Error::InvalidConfiguration(_))
+            )
+        };
+        let complete = DynamoDBSheepShed::builder()
+            .with_client(dynamodb_local_client())
+            .with_table_name("sheeps")
+            .with_shed_id(test_shed_id());
+        assert!(complete.clone().build().is_ok());
+        assert!(is_invalid(
+            DynamoDBSheepShed::builder()
+                .with_table_name("sheeps")
+                .with_shed_id(test_shed_id())
+                .build()
+        ));
+        assert!(is_invalid(
+            DynamoDBSheepShed::builder()
+                .with_client(dynamodb_local_client())
+                .with_shed_id(test_shed_id())
+                .build()
+        ));
+        assert!(is_invalid(complete.clone().with_table_name("").build()));
+        assert!(is_invalid(complete.clone().with_key_attribute("").build()));
+        assert!(is_invalid(
+            complete.clone().with_cursor_key("not a secret").build()
+        ));
+
+        // Without a cursor key, the shed cannot be listed
+        let sheep_shed = complete.build().unwrap();
+        let result = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(AsyncSheepShed::list_page(&sheep_shed, None, 1));
+        assert!(matches!(
+            result,
+            Err(sheep_shed::errors::Error::InvalidConfiguration(_))
+        ));
+    }
+
+    // The same templates must pass when the dog reads the counter
+    mod counter_mode {
+        use super::*;
+
+        macro_rules! impl_counter_test_template {
+            ($tn: tt) => {
+                #[test]
+                fn $tn() {
+                    let rt = tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()
+                        .unwrap();
+                    rt.block_on(async {
+                        rt.spawn_blocking(|| {
+                            let (_temp, sheep_shed) = prep_base_sheep_shed();
+                            let sheep_shed =
+                                sheep_shed.with_sheep_count_mode(SheepCountMode::Counter);
+                            sheep_shed::test_templates::$tn(sheep_shed)
+                        })
+                        .await
+                        .unwrap()
+                    })
+                }
+            };
+        }
+
+        impl_counter_test_template!(cannot_duplicate_sheep);
+        impl_counter_test_template!(sheep_shed_sheep_count);
+        impl_counter_test_template!(cannot_kill_inexistent_sheep);
+        impl_counter_test_template!(sheep_batch_insertion);
+        impl_counter_test_template!(optimistic_concurrency);
+
+        #[test]
+        fn sheep_transfer() {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                rt.spawn_blocking(|| {
+                    let (_temp, from) = prep_base_sheep_shed();
+                    let to = DynamoDBSheepShed::local_new(
+                        from.client.clone(),
+                        from.table_name.clone(),
+                        "other-shed".parse().unwrap(),
+                    )
+                    .with_sheep_count_mode(SheepCountMode::Counter);
+                    let from = from.with_sheep_count_mode(SheepCountMode::Counter);
+                    sheep_shed::test_templates::sheep_transfer(from, to)
+                })
+                .await
+                .unwrap()
+            })
+        }
+
+        #[test]
+        fn counter_follows_tombstones() {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                rt.spawn_blocking(|| {
+                    let (_temp, sheep_shed) = prep_base_sheep_shed();
+                    let mut sheep_shed = sheep_shed
+                        .with_tombstones(Duration::from_secs(3600))
+                        .with_sheep_count_mode(SheepCountMode::Counter);
+                    for tattoo in 1..=3 {
+                        SheepShed::add_sheep(
+                            &mut sheep_shed,
+                            Sheep::try_new(Tattoo(tattoo), Weight::MIN).unwrap(),
+                        )
+                        .unwrap();
+                    }
+                    SheepShed::kill_sheep(&mut sheep_shed, &Tattoo(1)).unwrap();
+                    assert!(SheepShed::kill_sheep(&mut sheep_shed, &Tattoo(1)).is_err());
+                    assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 2);
+                    tokio::runtime::Handle::current()
+                        .block_on(sheep_shed.resurrect_sheep(&Tattoo(1)))
+                        .unwrap();
+                    assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 3);
+                })
+                .await
+                .unwrap()
+            })
+        }
+    }
+
+    // The templates that scan the whole table must pass with any ScanConfig
+    mod tuned_scan {
+        use super::*;
+
+        fn tuned_scan_config() -> ScanConfig {
+            ScanConfig::default()
+                .with_segments(4)
+                .with_max_concurrent_segments(2)
+                .with_page_limit(3)
+                .with_projection(ScanProjection::Sheep)
+                .with_consistent_read(true)
+        }
+
+        macro_rules! impl_tuned_scan_test_template {
+            ($tn: tt) => {
+                #[test]
+                fn $tn() {
+                    let rt = tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()
+                        .unwrap();
+                    rt.block_on(async {
+                        rt.spawn_blocking(|| {
+                            let (_temp, sheep_shed) = prep_base_sheep_shed();
+                            let sheep_shed = sheep_shed.with_scan_config(tuned_scan_config());
+                            sheep_shed::test_templates::$tn(sheep_shed)
+                        })
+                        .await
+                        .unwrap()
+                    })
+                }
+            };
+        }
+
+        impl_tuned_scan_test_template!(sheep_shed_sheep_count);
+        impl_tuned_scan_test_template!(sheep_shed_iterator);
+        impl_tuned_scan_test_template!(sheep_details_preserved);
+
+        #[test]
+        fn sheep_shed_stream() {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                rt.spawn_blocking(|| {
+                    let (_temp, sheep_shed) = prep_base_sheep_shed();
+                    let sheep_shed = sheep_shed.with_scan_config(tuned_scan_config());
+                    tokio::runtime::Handle::current()
+                        .block_on(sheep_shed::test_templates::sheep_shed_stream(sheep_shed))
+                })
+                .await
+                .unwrap()
+            })
+        }
+    }
+
+    #[test]
+    fn invalid_items_are_not_fatal() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, mut sheep_shed) = prep_base_sheep_shed();
+                let handle = tokio::runtime::Handle::current();
+                SheepShed::add_sheep(
+                    &mut sheep_shed,
+                    Sheep::try_new(Tattoo(1), Weight::MIN).unwrap(),
+                )
+                .unwrap();
+                // A legacy sheep, without a weight
+                let mut item =
+                    sheep_shed.sheep_item(&Sheep::try_new(Tattoo(2), Weight::MIN).unwrap());
+                item.remove("weight");
+                handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .put_item()
+                            .table_name(&sheep_shed.table_name)
+                            .set_item(Some(item))
+                            .send(),
+                    )
+                    .unwrap();
+
+                assert!(matches!(
+                    SheepShed::get_sheep(&sheep_shed, &Tattoo(2)),
+                    Err(sheep_shed::errors::Error::GenericError(_))
+                ));
+                // The reads of the whole shed skip it
+                let sheeps = SheepShed::sheep_iter(&sheep_shed)
+                    .unwrap()
+                    .map(|sheep| sheep.tattoo)
+                    .collect::<Vec<_>>();
+                assert_eq!(sheeps, vec![Tattoo(1)]);
+                let page = SheepShed::list_page(&sheep_shed, None, 10).unwrap();
+                assert_eq!(page.sheeps.len(), 1);
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn sheep_count_reconciliation() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, sheep_shed) = prep_base_sheep_shed();
+                let mut sheep_shed = sheep_shed.with_sheep_count_mode(SheepCountMode::Counter);
+                let handle = tokio::runtime::Handle::current();
+                for tattoo in 1..=3 {
+                    SheepShed::add_sheep(
+                        &mut sheep_shed,
+                        Sheep::try_new(Tattoo(tattoo), Weight::MIN).unwrap(),
+                    )
+                    .unwrap();
+                }
+                // A sheep written without its counter, like before the counters existed
+                handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .put_item()
+                            .table_name(&sheep_shed.table_name)
+                            .set_item(Some(
+                                sheep_shed
+                                    .sheep_item(&Sheep::try_new(Tattoo(4), Weight::MIN).unwrap()),
+                            ))
+                            .send(),
+                    )
+                    .unwrap();
+                assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 3);
+
+                let drift = handle.block_on(sheep_shed.reconcile_sheep_count()).unwrap();
+                assert_eq!(
+                    drift,
+                    SheepCountDrift {
+                        counter: 3,
+                        scanned: 4
+                    }
+                );
+                assert_eq!(drift.drift(), -1);
+                assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 4);
+                // Nothing to fix anymore
+                assert_eq!(
+                    handle
+                        .block_on(sheep_shed.reconcile_sheep_count())
+                        .unwrap()
+                        .drift(),
+                    0
+                );
+                // The counter item is invisible to the shed
+                SheepShed::add_sheep(
+                    &mut sheep_shed,
+                    Sheep::try_new(Tattoo(0), Weight::MIN).unwrap(),
+                )
+                .unwrap();
+                assert_eq!(
+                    SheepShed::weight_history(&sheep_shed, &Tattoo(0))
+                        .unwrap()
+                        .len(),
+                    1
+                );
+                assert_eq!(SheepShed::sheep_iter(&sheep_shed).unwrap().count(), 5);
+                let sheep_shed = sheep_shed.with_sheep_count_mode(SheepCountMode::Scan);
+                assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 5);
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn tombstones_kill_and_resurrect() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, sheep_shed) = prep_base_sheep_shed();
+                let mut sheep_shed = sheep_shed
+                    .with_tombstones(Duration::from_secs(3600))
+                    .with_killer("wolf");
+                let handle = tokio::runtime::Handle::current();
+                for (tattoo, weight) in [(1, 120_000_000_007), (2, 100_000_000_003)] {
+                    SheepShed::add_sheep(
+                        &mut sheep_shed,
+                        Sheep::try_new(Tattoo(tattoo), Weight::from_ug(weight)).unwrap(),
+                    )
+                    .unwrap();
+                }
+                let before_kill = chrono::Utc::now();
+                let killed = SheepShed::kill_sheep(&mut sheep_shed, &Tattoo(1)).unwrap();
+                assert_eq!(killed.weight, Weight::from_ug(120_000_000_007));
+
+                // The tombstone is invisible...
+                assert!(SheepShed::get_sheep(&sheep_shed, &Tattoo(1))
+                    .unwrap()
+                    .is_none());
+                assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 1);
+                assert_eq!(
+                    SheepShed::sheep_iter(&sheep_shed)
+                        .unwrap()
+                        .map(|s| s.tattoo)
+                        .collect::<Vec<_>>(),
+                    vec![Tattoo(2)]
+                );
+                assert_eq!(
+                    SheepShed::heaviest_matching(&sheep_shed, WeightCriteria::PrimeUg)
+                        .unwrap()
+                        .map(|s| s.tattoo),
+                    Some(Tattoo(2))
+                );
+                assert!(SheepShed::weight_history(&sheep_shed, &Tattoo(1))
+                    .is_err_and(|e| matches!(e, sheep_shed::errors::Error::SheepNotPresent(_))));
+                assert!(
+                    SheepShed::update_weight(&mut sheep_shed, &Tattoo(1), Weight::MIN)
+                        .is_err_and(|e| matches!(e, sheep_shed::errors::Error::SheepNotPresent(_)))
+                );
+                assert!(SheepShed::kill_sheep(&mut sheep_shed, &Tattoo(1))
+                    .is_err_and(|e| matches!(e, sheep_shed::errors::Error::SheepNotPresent(_))));
+                // ...but keeps its tattoo
+                assert!(SheepShed::add_sheep(
+                    &mut sheep_shed,
+                    Sheep::try_new(Tattoo(1), Weight::MIN).unwrap()
+                )
+                .is_err_and(|e| matches!(e, sheep_shed::errors::Error::SheepDuplicationError(_))));
+
+                // ...and can be audited
+                let killed = handle
+                    .block_on(sheep_shed.list_killed(None, 10))
+                    .unwrap()
+                    .killed;
+                assert_eq!(killed.len(), 1);
+                assert_eq!(killed[0].sheep.tattoo, Tattoo(1));
+                assert_eq!(killed[0].sheep.weight, Weight::from_ug(120_000_000_007));
+                assert_eq!(killed[0].killed_by.as_deref(), Some("wolf"));
+                assert!(killed[0].killed_at >= before_kill);
+
+                // The tombstone and the history expire after the retention
+                let partition = handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .query()
+                            .table_name(&sheep_shed.table_name)
+                            .key_condition_expression("tattoo = :tattoo")
+                            .expression_attribute_values(
+                                ":tattoo",
+                                AttributeValue::N("1".to_owned()),
+                            )
+                            .send(),
+                    )
+                    .unwrap()
+                    .items
+                    .unwrap();
+                assert_eq!(partition.len(), 2);
+                let expected_expiry = before_kill.timestamp() + 3600;
+                for item in partition {
+                    let expire_at: i64 = item[EXPIRE_AT_ATTRIBUTE].as_n().unwrap().parse().unwrap();
+                    assert!((expected_expiry..expected_expiry + 60).contains(&expire_at));
+                }
+
+                // Resurrection
+                assert!(handle
+                    .block_on(sheep_shed.resurrect_sheep(&Tattoo(2)))
+                    .is_err_and(|e| matches!(
+                        e,
+                        sheep_shed::errors::Error::SheepDuplicationError(_)
+                    )));
+                assert!(handle
+                    .block_on(sheep_shed.resurrect_sheep(&Tattoo(3)))
+                    .is_err_and(|e| matches!(e, sheep_shed::errors::Error::SheepNotPresent(_))));
+                let resurrected = handle
+                    .block_on(sheep_shed.resurrect_sheep(&Tattoo(1)))
+                    .unwrap();
+                assert_eq!(resurrected.version, 2);
+                assert_eq!(
+                    SheepShed::get_sheep(&sheep_shed, &Tattoo(1))
+                        .unwrap()
+                        .unwrap()
+                        .version,
+                    2
+                );
+                assert_eq!(
+                    SheepShed::heaviest_matching(&sheep_shed, WeightCriteria::PrimeUg)
+                        .unwrap()
+                        .map(|s| s.tattoo),
+                    Some(Tattoo(1))
+                );
+                assert_eq!(
+                    SheepShed::weight_history(&sheep_shed, &Tattoo(1))
+                        .unwrap()
+                        .len(),
+                    1
+                );
+                assert!(handle
+                    .block_on(sheep_shed.list_killed(None, 10))
+                    .unwrap()
+                    .killed
+                    .is_empty());
+                assert!(handle
+                    .block_on(sheep_shed._weigh_in_items(&Tattoo(1)))
+                    .unwrap()
+                    .iter()
+                    .all(|item| !item.contains_key(EXPIRE_AT_ATTRIBUTE)));
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn killed_sheeps_are_paged() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, sheep_shed) = prep_base_sheep_shed();
+                let mut sheep_shed = sheep_shed.with_tombstones(Duration::from_secs(3600));
+                let handle = tokio::runtime::Handle::current();
+                for tattoo in 1..=5 {
+                    SheepShed::add_sheep(
+                        &mut sheep_shed,
+                        Sheep::try_new(Tattoo(tattoo), Weight::from_ug(100_000_000_000)).unwrap(),
+                    )
+                    .unwrap();
+                    SheepShed::kill_sheep(&mut sheep_shed, &Tattoo(tattoo)).unwrap();
+                }
+                // A tombstone that does not decode does not stop the listing
+                let mut item = sheep_shed.sheep_item(
+                    &Sheep::try_new(Tattoo(6), Weight::from_ug(100_000_000_000)).unwrap(),
+                );
+                item.insert("weight".to_owned(), AttributeValue::S("heavy".to_owned()));
+                item.insert(
+                    KILLED_AT_ATTRIBUTE.to_owned(),
+                    AttributeValue::S("2000-01-01T00:00:00.000000000Z".to_owned()),
+                );
+                item.insert(
+                    SHED_KILLED_ATTRIBUTE.to_owned(),
+                    AttributeValue::S(test_shed_id().to_string()),
+                );
+                handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .put_item()
+                            .table_name(&sheep_shed.table_name)
+                            .set_item(Some(item))
+                            .send(),
+                    )
+                    .unwrap();
+
+                let mut pages = vec![];
+                let mut cursor = None;
+                loop {
+                    let page = handle
+                        .block_on(sheep_shed.list_killed(cursor.as_ref(), 2))
+                        .unwrap();
+                    pages.push(
+                        page.killed
+                            .into_iter()
+                            .map(|k| k.sheep.tattoo.0)
+                            .collect::<Vec<_>>(),
+                    );
+                    let Some(next_cursor) = page.next_cursor else {
+                        break;
+                    };
+                    cursor = Some(next_cursor);
+                }
+                // The most recently killed first
+                assert_eq!(pages, vec![vec![5, 4], vec![3, 2], vec![1]]);
+
+                // The cursors are bound to the shed and to the listing
+                let cursor = handle
+                    .block_on(sheep_shed.list_killed(None, 1))
+                    .unwrap()
+                    .next_cursor
+                    .unwrap();
+                let other_shed = DynamoDBSheepShed::local_new(
+                    sheep_shed.client.clone(),
+                    sheep_shed.table_name.clone(),
+                    "other-farm".parse().unwrap(),
+                );
+                assert!(handle
+                    .block_on(other_shed.list_killed(Some(&cursor), 1))
+                    .is_err_and(|e| matches!(e, sheep_shed::errors::Error::InvalidCursor(_))));
+                assert!(SheepShed::list_page(&sheep_shed, Some(&cursor), 1)
+                    .is_err_and(|e| matches!(e, sheep_shed::errors::Error::InvalidCursor(_))));
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[tokio::test]
+    async fn missing_table_is_reported() {
+        let sheep_shed = DynamoDBSheepShed::local_new(
+            dynamodb_local_client(),
+            format!("missing-{}", rand::random::<u64>()),
+            test_shed_id(),
+        );
+        assert!(matches!(
+            AsyncSheepShed::get_sheep(&sheep_shed, &Tattoo(1)).await,
+            Err(sheep_shed::errors::Error::TableNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn legacy_item_deserializes() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, sheep_shed) = prep_base_sheep_shed();
+                // An item written before the optional fields of the Sheep existed
+                tokio::runtime::Handle::current()
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .put_item()
+                            .table_name(&sheep_shed.table_name)
+                            .item("tattoo", AttributeValue::N("1".to_owned()))
+                            .item(SORT_KEY_ATTRIBUTE, sheep_sort_key(&test_shed_id()))
+                            .item("weight", AttributeValue::N("100000000000".to_owned()))
+                            .send(),
+                    )
+                    .unwrap();
+                let sheep = SheepShed::get_sheep(&sheep_shed, &Tattoo(1))
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(sheep.weight, Weight::from_ug(100_000_000_000));
+                assert_eq!(sheep.name, None);
+                assert!(sheep.tags.is_empty());
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn sheds_share_a_table() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, mut shed_a) = prep_base_sheep_shed();
+                let mut shed_b = DynamoDBSheepShed::local_new(
+                    shed_a.client.clone(),
+                    shed_a.table_name.clone(),
+                    "other-shed".parse().unwrap(),
+                );
+                let weight_a = Weight::from_ug(120_000_000_007);
+                let weight_b = Weight::from_ug(100_000_000_003);
+                // The same tattoo in both sheds
+                SheepShed::add_sheep(&mut shed_a, Sheep::try_new(Tattoo(1), weight_a).unwrap())
+                    .unwrap();
+                SheepShed::add_sheep(&mut shed_b, Sheep::try_new(Tattoo(1), weight_b).unwrap())
+                    .unwrap();
+                SheepShed::add_sheep(&mut shed_b, Sheep::try_new(Tattoo(2), Weight::MIN).unwrap())
+                    .unwrap();
+
+                assert_eq!(SheepShed::sheep_count(&shed_a).unwrap(), 1);
+                assert_eq!(SheepShed::sheep_count(&shed_b).unwrap(), 2);
+                assert_eq!(
+                    SheepShed::get_sheep(&shed_a, &Tattoo(1))
+                        .unwrap()
+                        .unwrap()
+                        .weight,
+                    weight_a
+                );
+                assert_eq!(
+                    SheepShed::get_sheep(&shed_b, &Tattoo(1))
+                        .unwrap()
+                        .unwrap()
+                        .weight,
+                    weight_b
+                );
+                assert!(SheepShed::get_sheep(&shed_a, &Tattoo(2)).unwrap().is_none());
+                assert_eq!(
+                    SheepShed::heaviest_matching(&shed_b, WeightCriteria::PrimeUg)
+                        .unwrap()
+                        .map(|s| s.weight),
+                    Some(weight_b)
+                );
+                assert_eq!(
+                    SheepShed::list_page(&shed_a, None, 10)
+                        .unwrap()
+                        .sheeps
+                        .len(),
+                    1
+                );
+                // A cursor is bound to the shed that issued it
+                let cursor = SheepShed::list_page(&shed_b, None, 1)
+                    .unwrap()
+                    .next_cursor
+                    .unwrap();
+                assert!(matches!(
+                    SheepShed::list_page(&shed_a, Some(&cursor), 1),
+                    Err(sheep_shed::errors::Error::InvalidCursor(_))
+                ));
+
+                SheepShed::update_weight(&mut shed_a, &Tattoo(1), Weight::MIN).unwrap();
+                assert_eq!(
+                    SheepShed::weight_history(&shed_a, &Tattoo(1))
+                        .unwrap()
+                        .len(),
+                    2
+                );
+                assert_eq!(
+                    SheepShed::weight_history(&shed_b, &Tattoo(1))
+                        .unwrap()
+                        .len(),
+                    1
+                );
+
+                SheepShed::kill_sheep(&mut shed_a, &Tattoo(1)).unwrap();
+                assert_eq!(SheepShed::sheep_count(&shed_a).unwrap(), 0);
+                assert_eq!(SheepShed::sheep_count(&shed_b).unwrap(), 2);
+                assert_eq!(
+                    SheepShed::weight_history(&shed_b, &Tattoo(1))
+                        .unwrap()
+                        .len(),
+                    1
+                );
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn sheep_transfer() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, from) = prep_base_sheep_shed();
+                let to = DynamoDBSheepShed::local_new(
+                    from.client.clone(),
+                    from.table_name.clone(),
+                    "other-shed".parse().unwrap(),
+                );
+                sheep_shed::test_templates::sheep_transfer(from, to)
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn transfer_history_longer_than_a_transaction() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (_temp, mut from) = prep_base_sheep_shed();
+                let mut to = DynamoDBSheepShed::local_new(
+                    from.client.clone(),
+                    from.table_name.clone(),
+                    "other-shed".parse().unwrap(),
+                );
+                SheepShed::add_sheep(&mut from, Sheep::try_new(Tattoo(1), Weight::MIN).unwrap())
+                    .unwrap();
+                for ug in 1..=TRANSACT_WRITE_MAX_ITEMS as u64 {
+                    SheepShed::update_weight(
+                        &mut from,
+                        &Tattoo(1),
+                        Weight::from_ug(Weight::MIN.as_ug() + ug),
+                    )
+                    .unwrap();
+                }
+                let history = SheepShed::weight_history(&from, &Tattoo(1)).unwrap();
+                assert_eq!(history.len(), TRANSACT_WRITE_MAX_ITEMS + 1);
+
+                SheepShed::transfer_sheep(&mut from, &mut to, &Tattoo(1)).unwrap();
+                assert_eq!(SheepShed::weight_history(&to, &Tattoo(1)).unwrap(), history);
+                assert!(tokio::runtime::Handle::current()
+                    .block_on(from._weigh_in_items(&Tattoo(1)))
+                    .unwrap()
+                    .is_empty());
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    fn prep_tenant_sheep_shed(tenant_id: &str, shed_id: &str) -> (TempTable, TenantSheepShed) {
+        let client = dynamodb_local_client();
+        let table_name = format!("{}", rand::random::<u64>());
+        let temp_table = TempTable::new(client.clone(), &table_name);
+        let sheep_shed = TenantSheepShed::local_new(
+            client,
+            table_name,
+            tenant_id.parse().unwrap(),
+            shed_id.parse().unwrap(),
+        );
+        (temp_table, sheep_shed)
+    }
+
+    // The TenantSheepShed only delegates, a few templates are enough to check it.
+    mod tenant_mode {
+        use super::*;
+
+        macro_rules! impl_tenant_test_template {
+            ($tn: tt) => {
+                #[test]
+                fn $tn() {
+                    let rt = tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()
+                        .unwrap();
+                    rt.block_on(async {
+                        rt.spawn_blocking(|| {
+                            let (_temp, sheep_shed) =
+                                prep_tenant_sheep_shed("test-tenant", "test-shed");
+                            sheep_shed::test_templates::$tn(sheep_shed::SyncAdapter(sheep_shed))
+                        })
+                        .await
+                        .unwrap()
+                    })
+                }
+            };
+        }
+
+        impl_tenant_test_template!(sheep_shed_sheep_count);
+        impl_tenant_test_template!(cannot_kill_inexistent_sheep);
+        impl_tenant_test_template!(sheep_shed_pagination);
+        impl_tenant_test_template!(heaviest_matching_sheep);
+        impl_tenant_test_template!(sheep_weight_history);
+        impl_tenant_test_template!(optimistic_concurrency);
+
+        #[test]
+        fn sheep_transfer() {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                rt.spawn_blocking(|| {
+                    let (temp, from) = prep_tenant_sheep_shed("test-tenant", "test-shed");
+                    let to = TenantSheepShed::local_new(
+                        temp.client.clone(),
+                        temp.table_name.clone(),
+                        "test-tenant".parse().unwrap(),
+                        "other-shed".parse().unwrap(),
+                    );
+                    sheep_shed::test_templates::sheep_transfer(
+                        sheep_shed::SyncAdapter(from),
+                        sheep_shed::SyncAdapter(to),
+                    )
+                })
+                .await
+                .unwrap()
+            })
+        }
+    }
+
+    #[test]
+    fn tenants_are_isolated() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (temp, alice) = prep_tenant_sheep_shed("alice", "farm");
+                tokio::runtime::Handle::current().block_on(tenants_are_isolated_async(
+                    alice,
+                    temp.client.clone(),
+                    temp.table_name.clone(),
+                ))
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    async fn tenants_are_isolated_async(
+        mut alice: TenantSheepShed,
+        client: Client,
+        table_name: String,
+    ) {
+        let mut bob = TenantSheepShed::local_new(
+            client.clone(),
+            table_name.clone(),
+            "bob".parse().unwrap(),
+            "farm".parse().unwrap(),
+        );
+        // The public shed with the same id as the sheds of the tenants
+        let mut public = DynamoDBSheepShed::local_new(client, table_name, "farm".parse().unwrap());
+        let weight_alice = Weight::from_ug(120_000_000_007);
+        let weight_bob = Weight::from_ug(100_000_000_003);
+        let weight_public = Weight::from_ug(110_000_000_017);
+        alice
+            .add_sheep(Sheep::try_new(Tattoo(1), weight_alice).unwrap())
+            .await
+            .unwrap();
+        bob.add_sheep(Sheep::try_new(Tattoo(1), weight_bob).unwrap())
+            .await
+            .unwrap();
+        AsyncSheepShed::add_sheep(
+            &mut public,
+            Sheep::try_new(Tattoo(1), weight_public).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        for (sheep_shed, weight) in [(&alice, weight_alice), (&bob, weight_bob)] {
+            assert_eq!(sheep_shed.sheep_count().await.unwrap(), 1);
+            assert_eq!(
+                sheep_shed
+                    .get_sheep(&Tattoo(1))
+                    .await
+                    .unwrap()
+                    .map(|s| s.weight),
+                Some(weight)
+            );
+            let page = sheep_shed.list_page(None, 10).await.unwrap();
+            assert_eq!(page.sheeps.len(), 1);
+            assert_eq!(page.sheeps[0].weight, weight);
+            assert_eq!(
+                sheep_shed
+                    .heaviest_matching(WeightCriteria::PrimeUg)
+                    .await
+                    .unwrap()
+                    .map(|s| s.weight),
+                Some(weight)
+            );
+        }
+        assert_eq!(AsyncSheepShed::sheep_count(&public).await.unwrap(), 1);
+
+        alice.kill_sheep(&Tattoo(1)).await.unwrap();
+        assert_eq!(alice.sheep_count().await.unwrap(), 0);
+        assert!(alice
+            .kill_sheep(&Tattoo(1))
+            .await
+            .is_err_and(|e| matches!(e, sheep_shed::errors::Error::SheepNotPresent(_))));
+        assert_eq!(bob.sheep_count().await.unwrap(), 1);
+        assert_eq!(AsyncSheepShed::sheep_count(&public).await.unwrap(), 1);
+        assert_eq!(bob.weight_history(&Tattoo(1)).await.unwrap().len(), 1);
+
+        // A sheep cannot be transferred to another tenant
+        bob.add_sheep(Sheep::try_new(Tattoo(2), weight_bob).unwrap())
+            .await
+            .unwrap();
+        assert!(bob
+            .transfer_sheep(&mut alice, &Tattoo(2))
+            .await
+            .is_err_and(|e| matches!(e, sheep_shed::errors::Error::ForeignTenant(_))));
+        assert_eq!(bob.sheep_count().await.unwrap(), 2);
+        assert_eq!(alice.sheep_count().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn tenant_cursors_hold_no_foreign_key() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let (temp, alice) = prep_tenant_sheep_shed("alice", "farm");
+                tokio::runtime::Handle::current().block_on(
+                    tenant_cursors_hold_no_foreign_key_async(
+                        alice,
+                        temp.client.clone(),
+                        temp.table_name.clone(),
+                    ),
+                )
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    async fn tenant_cursors_hold_no_foreign_key_async(
+        mut alice: TenantSheepShed,
+        client: Client,
+        table_name: String,
+    ) {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+        let mut bob = TenantSheepShed::local_new(
+            client.clone(),
+            table_name.clone(),
+            "bob".parse().unwrap(),
+            "farm".parse().unwrap(),
+        );
+        let mut public = DynamoDBSheepShed::local_new(client, table_name, "farm".parse().unwrap());
+        // The sheeps of the others surround the ones of alice in the table,
+        // with weigh-ins, prime and composite weights
+        for t in 1..=12 {
+            let weight = Weight::from_ug(if t % 2 == 0 {
+                100_000_000_003
+            } else {
+                100_000_000_000 + t
+            });
+            let sheep = Sheep::try_new(Tattoo(t), weight).unwrap();
+            bob.add_sheep(sheep.clone()).await.unwrap();
+            AsyncSheepShed::add_sheep(&mut public, sheep.clone())
+                .await
+                .unwrap();
+            if t % 3 == 0 {
+                alice.add_sheep(sheep).await.unwrap();
+                alice.update_weight(&Tattoo(t), Weight::MIN).await.unwrap();
+            }
+        }
+
+        let mut tattoos = vec![];
+        let mut cursor = None;
+        loop {
+            let page = alice.list_page(cursor.as_ref(), 1).await.unwrap();
+            tattoos.extend(page.sheeps.into_iter().map(|s| s.tattoo.0));
+            let Some(next_cursor) = page.next_cursor else {
+                break;
+            };
+            let cursor_string = next_cursor.to_string();
+            let (payload, _) = cursor_string.split_once('.').unwrap();
+            let payload = String::from_utf8(URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+            // Neither a tenant, nor a shed, nor a sort key
+            for foreign in ["alice", "bob", "farm", "#"] {
+                assert!(!payload.contains(foreign), "{foreign} in {payload}");
+            }
+            // Another tenant cannot use it, even with the same ShedId
+            assert!(bob
+                .list_page(Some(&next_cursor), 1)
+                .await
+                .is_err_and(|e| matches!(e, sheep_shed::errors::Error::InvalidCursor(_))));
+            cursor = Some(next_cursor);
+        }
+        tattoos.sort();
+        assert_eq!(tattoos, vec![3, 6, 9, 12]);
+    }
+
+    #[test]
+    fn heaviest_matching_without_index() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let client = dynamodb_local_client();
+                let table_name = format!("{}", rand::random::<u64>());
+                let _temp = TempTable::without_indexes(client.clone(), &table_name);
+                let sheep_shed = DynamoDBSheepShed::local_new(client, table_name, test_shed_id());
+                assert!(
+                    SheepShed::heaviest_matching(&sheep_shed, WeightCriteria::PrimeUg).is_err_and(
+                        |e| matches!(e, sheep_shed::errors::Error::IndexNotAvailable(_))
+                    )
+                );
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn ensure_table_is_idempotent() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let client = dynamodb_local_client();
+                let table_name = format!("{}", rand::random::<u64>());
+                let _temp = TempTable::without_indexes(client.clone(), &table_name);
+                let table = SheepTable::new(client, table_name);
+                let handle = tokio::runtime::Handle::current();
+                assert_eq!(
+                    handle.block_on(table.ensure_table()).unwrap(),
+                    vec![
+                        TableChange::TimeToLiveEnabled,
+                        TableChange::WeightIndexCreated,
+                        TableChange::KilledIndexCreated
+                    ]
+                );
+                assert!(handle.block_on(table.ensure_table()).unwrap().is_empty());
+                // A table keyed by another attribute is not a sheep table
+                assert!(matches!(
+                    handle.block_on(table.with_key_attribute("sheep_id").ensure_table()),
+                    Err(sheep_shed::errors::Error::InvalidConfiguration(_))
+                ));
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn migrations_backfill_the_indexes_and_counters() {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            rt.spawn_blocking(|| {
+                let client = dynamodb_local_client();
+                let table_name = format!("{}", rand::random::<u64>());
+                let _temp = TempTable::without_indexes(client.clone(), &table_name);
+                let sheep_shed = DynamoDBSheepShed::local_new(client, table_name, test_shed_id());
+                let handle = tokio::runtime::Handle::current();
+                // A sheep written before the weight index existed
+                let sheep = Sheep::try_new(Tattoo(1), Weight::from_ug(80_000_000_009)).unwrap();
+                let mut item = sheep_shed.sheep_item(&sheep);
+                item.remove(SHED_PRIME_WEIGHT_ATTRIBUTE);
+                handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .put_item()
+                            .table_name(&sheep_shed.table_name)
+                            .set_item(Some(item))
+                            .send(),
+                    )
+                    .unwrap();
+
+                // A tombstone written before the killed index existed
+                let mut item = sheep_shed.sheep_item(
+                    &Sheep::try_new(Tattoo(2), Weight::from_ug(80_000_000_000)).unwrap(),
+                );
+                item.remove(SHED_PRIME_WEIGHT_ATTRIBUTE);
+                item.insert(
+                    KILLED_AT_ATTRIBUTE.to_owned(),
+                    AttributeValue::S("2000-01-01T00:00:00.000000000Z".to_owned()),
+                );
+                handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .put_item()
+                            .table_name(&sheep_shed.table_name)
+                            .set_item(Some(item))
+                            .send(),
+                    )
+                    .unwrap();
+
+                let table = sheep_shed.table();
+                handle.block_on(table.ensure_table()).unwrap();
+                assert_eq!(handle.block_on(table.schema_version()).unwrap(), 0);
+                assert_eq!(
+                    handle.block_on(table.migrate()).unwrap(),
+                    MIGRATIONS.to_vec()
+                );
+                assert_eq!(handle.block_on(table.schema_version()).unwrap(), 3);
+                assert!(handle.block_on(table.migrate()).unwrap().is_empty());
+                // Written without its counter, it is counted by the migration
+                let sheep_shed = sheep_shed.with_sheep_count_mode(SheepCountMode::Counter);
+                assert_eq!(SheepShed::sheep_count(&sheep_shed).unwrap(), 1);
+
+                let item = handle
+                    .block_on(
+                        sheep_shed
+                            .client
+                            .get_item()
+                            .table_name(&sheep_shed.table_name)
+                            .set_key(Some(sheep_shed.sheep_key(&Tattoo(1))))
+                            .send(),
+                    )
+                    .unwrap()
+                    .item
+                    .unwrap();
+                assert_eq!(
+                    item.get(SHED_PRIME_WEIGHT_ATTRIBUTE),
+                    Some(&shed_prime_weight_value(&test_shed_id(), &sheep.weight))
+                );
+                let killed = handle
+                    .block_on(sheep_shed.list_killed(None, 10))
+                    .unwrap()
+                    .killed;
+                assert_eq!(
+                    killed.iter().map(|k| &k.sheep.tattoo).collect::<Vec<_>>(),
+                    vec![&Tattoo(2)]
+                );
+            })
+            .await
+            .unwrap()
+        })
+    }
+
+    // The async test templates directly use the AsyncSheepShed implementation,
+    // only the TempTable needs a blocking thread.
+    macro_rules! impl_async_test_template {
+        ($tn: tt) => {
+            #[test]
+            fn $tn() {
+                let rt = tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                rt.block_on(async {
+                    rt.spawn_blocking(|| {
+                        let (_temp, sheep_shed) = prep_base_sheep_shed();
+                        tokio::runtime::Handle::current()
+                            .block_on(sheep_shed::test_templates::$tn(sheep_shed))
+                    })
+                    .await
+                    .unwrap()
+                })
+            }
+        };
+    }
+
+    impl_async_test_template!(sheep_shed_stream);
 }
@@ -0,0 +1,290 @@
+//! The retries of the DynamoDB calls of the [crate::DynamoDBSheepShed], see [RetryConfig]
+
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use aws_sdk_dynamodb::{
+    config::http::HttpResponse,
+    error::{DisplayErrorContext, ProvideErrorMetadata, SdkError},
+    operation::transact_write_items::{
+        builders::TransactWriteItemsFluentBuilder, TransactWriteItemsError,
+        TransactWriteItemsOutput,
+    },
+};
+
+use crate::builder::{env_var, invalid};
+
+/// What went wrong with a DynamoDB call, beyond the errors each call handles itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    Throttled,
+    Transient,
+    TableNotFound,
+    AccessDenied,
+    Other,
+}
+
+impl ErrorKind {
+    fn of<E: ProvideErrorMetadata>(e: &SdkError<E, HttpResponse>) -> Self {
+        match e {
+            SdkError::TimeoutError(_)
+            | SdkError::DispatchFailure(_)
+            | SdkError::ResponseError(_) => Self::Transient,
+            SdkError::ServiceError(se) => match se.err().code() {
+                Some(
+                    "ProvisionedThroughputExceededException"
+                    | "ThrottlingException"
+                    | "RequestLimitExceeded",
+                ) => Self::Throttled,
+                // A transaction cancelled because one of its items was throttled
+                Some("TransactionCanceledException")
+                    if se
+                        .err()
+                        .message()
+                        .is_some_and(|m| m.contains("ThrottlingError")) =>
+                {
+                    Self::Throttled
+                }
+                Some("ResourceNotFoundException") => Self::TableNotFound,
+                Some("AccessDeniedException" | "UnrecognizedClientException") => Self::AccessDenied,
+                Some("InternalServerError" | "ServiceUnavailable") => Self::Transient,
+                _ if se.raw().status().is_server_error() => Self::Transient,
+                _ => Self::Other,
+            },
+            _ => Self::Other,
+        }
+    }
+
+    fn is_retryable(self) -> bool {
+        matches!(self, Self::Throttled | Self::Transient)
+    }
+}
+
+/// Convert the error of a DynamoDB call into the [sheep_shed::errors::Error]
+/// of its kind, [sheep_shed::errors::Error::GenericError] if it has none
+pub(crate) fn database_error<E>(e: SdkError<E, HttpResponse>) -> sheep_shed::errors::Error
+where
+    E: ProvideErrorMetadata + std::error::Error + 'static,
+{
+    let kind = ErrorKind::of(&e);
+    let err_string = match &e {
+        SdkError::ServiceError(se) => {
+            let err = se.err();
+            format!("{err} ({:?}: {:?})", err.code(), err.message())
+        }
+        _ => DisplayErrorContext(&e).to_string(),
+    };
+    log::error!("{err_string}");
+    match kind {
+        ErrorKind::Throttled => sheep_shed::errors::Error::Throttled(err_string),
+        ErrorKind::Transient => sheep_shed::errors::Error::Transient(err_string),
+        ErrorKind::TableNotFound => sheep_shed::errors::Error::TableNotFound(err_string),
+        ErrorKind::AccessDenied => sheep_shed::errors::Error::AccessDenied(err_string),
+        ErrorKind::Other => sheep_shed::errors::Error::GenericError(err_string),
+    }
+}
+
+/// How a [crate::DynamoDBSheepShed] retries its DynamoDB calls, including each
+/// page of its scans, when they are throttled or fail transiently
+///
+/// The delay before each retry is drawn at random between zero and an exponential
+/// backoff (`base_delay` doubled at each attempt, capped at `max_delay`), so
+/// concurrent callers do not retry all at once.
+///
+/// This comes on top of the retries of the [aws_sdk_dynamodb::Client] itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryConfig {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Creates the default [RetryConfig] overridden by the environment variables
+    /// that are set:
+    /// - `RETRY_MAX_ATTEMPTS`, see [RetryConfig::with_max_attempts]
+    /// - `RETRY_BASE_DELAY_MS`, see [RetryConfig::with_base_delay]
+    /// - `RETRY_MAX_DELAY_MS`, see [RetryConfig::with_max_delay]
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if one of
+    /// the environment variables is set to an invalid value.
+    pub fn from_env() -> Result<Self, sheep_shed::errors::Error> {
+        let config = Self::default();
+        let config = match env_var("RETRY_MAX_ATTEMPTS", "a number of attempts")? {
+            Some(0) => Err(invalid("RETRY_MAX_ATTEMPTS", "positive"))?,
+            Some(max_attempts) => config.with_max_attempts(max_attempts),
+            None => config,
+        };
+        let config = match env_var("RETRY_BASE_DELAY_MS", "a number of milliseconds")? {
+            Some(ms) => config.with_base_delay(Duration::from_millis(ms)),
+            None => config,
+        };
+        Ok(
+            match env_var("RETRY_MAX_DELAY_MS", "a number of milliseconds")? {
+                Some(ms) => config.with_max_delay(Duration::from_millis(ms)),
+                None => config,
+            },
+        )
+    }
+
+    /// Make at most `max_attempts` attempts of each call, 1 disables the retries
+    /// # Panics
+    /// Panics if `max_attempts` is 0.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "a call is attempted at least once");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// The backoff before the first retry, doubled at each following one
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// The maximum backoff before a retry
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// The random delay before the retry following the given (0-based) `attempt`
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << attempt.min(31))
+            .min(self.max_delay);
+        let random = RandomState::new().build_hasher().finish();
+        backoff.mul_f64(random as f64 / u64::MAX as f64)
+    }
+
+    /// Make the DynamoDB `call` until it succeeds, fails with an error that is
+    /// not worth retrying, or `max_attempts` are made. The error of the last
+    /// attempt is returned as is.
+    pub(crate) async fn call<T, E, F, Fut>(
+        &self,
+        operation: &str,
+        mut call: F,
+    ) -> Result<T, SdkError<E, HttpResponse>>
+    where
+        E: ProvideErrorMetadata,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match call().await {
+                Err(e) if attempt + 1 < self.max_attempts && ErrorKind::of(&e).is_retryable() => {
+                    let delay = self.delay(attempt);
+                    attempt += 1;
+                    log::warn!(
+                        "{operation} failed ({:?}), retrying in {delay:?} (attempt {attempt})",
+                        ErrorKind::of(&e)
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Make the `TransactWriteItems` call like [RetryConfig::call], with a
+    /// `ClientRequestToken` of its own so that a retry of a transaction that
+    /// went through without us knowing is not applied twice.
+    pub(crate) async fn transact_write_items(
+        &self,
+        transact_write_items: TransactWriteItemsFluentBuilder,
+    ) -> Result<TransactWriteItemsOutput, SdkError<TransactWriteItemsError, HttpResponse>> {
+        let transact_write_items =
+            transact_write_items.client_request_token(client_request_token());
+        self.call("transact_write_items", || {
+            transact_write_items.clone().send()
+        })
+        .await
+    }
+}
+
+/// A random idempotency token of 32 hexadecimal digits, DynamoDB accepts up to 36
+fn client_request_token() -> String {
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use aws_sdk_dynamodb::operation::get_item::GetItemError;
+
+    use super::*;
+
+    #[test]
+    fn delays_are_bounded() {
+        let config = RetryConfig::default()
+            .with_base_delay(Duration::from_millis(10))
+            .with_max_delay(Duration::from_millis(100));
+        for attempt in 0..40 {
+            let cap = Duration::from_millis(10 * (1 << attempt.min(4))).min(config.max_delay);
+            assert!(config.delay(attempt) <= cap);
+        }
+    }
+
+    #[test]
+    fn client_request_tokens_are_unique() {
+        let token = client_request_token();
+        assert!(token.len() <= 36);
+        assert_ne!(token, client_request_token());
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let config = RetryConfig::default()
+            .with_max_attempts(4)
+            .with_base_delay(Duration::from_millis(1));
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = config
+            .call("timeout", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(SdkError::<GetItemError, HttpResponse>::timeout_error(
+                    "timed out",
+                ))
+            })
+            .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+        assert!(matches!(
+            database_error(result.unwrap_err()),
+            sheep_shed::errors::Error::Transient(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_retried() {
+        let config = RetryConfig::default().with_max_attempts(4);
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = config
+            .call("construction", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(SdkError::<GetItemError, HttpResponse>::construction_failure("invalid request"))
+            })
+            .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            database_error(result.unwrap_err()),
+            sheep_shed::errors::Error::GenericError(_)
+        ));
+    }
+}
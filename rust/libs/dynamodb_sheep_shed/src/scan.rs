@@ -0,0 +1,318 @@
+//! The parallel scan of the [DynamoDBSheepShed], see [ScanConfig]
+
+use std::{cmp::min, str::FromStr, sync::Arc, time::Instant};
+
+use aws_sdk_dynamodb::{operation::scan::ScanOutput, types::Select};
+use futures::{channel::mpsc, SinkExt, Stream};
+use tokio::sync::Semaphore;
+
+use crate::{
+    builder::{env_var, invalid},
+    database_error, sheep_sort_key, DynamoDBSheepShed, KILLED_AT_ATTRIBUTE, SORT_KEY_ATTRIBUTE,
+};
+
+/// The default maximum number of segments of a parallel scan running at the same time
+pub const DEFAULT_MAX_CONCURRENT_SEGMENTS: usize = 64;
+
+/// The attributes of the serialized [sheep_shed::Sheep]s, the only ones retrieved
+/// by a scan with [ScanProjection::Sheep] along with the key attribute of the table
+const SHEEP_ATTRIBUTES: [&str; 8] = [
+    "tattoo",
+    "weight",
+    "name",
+    "breed",
+    "birth_date",
+    "sex",
+    "tags",
+    crate::VERSION_ATTRIBUTE,
+];
+
+/// The attributes retrieved by a parallel scan of the [sheep_shed::Sheep]s
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanProjection {
+    /// All the attributes of the items
+    #[default]
+    All,
+    /// Only the attributes of the [sheep_shed::Sheep]s, leaving out the ones the
+    /// [DynamoDBSheepShed] maintains for itself (e.g. [crate::SHED_PRIME_WEIGHT_ATTRIBUTE]).
+    ///
+    /// The scans then select `SPECIFIC_ATTRIBUTES` instead of `ALL_ATTRIBUTES`, an IAM
+    /// policy with a `dynamodb:Select` condition must allow both.
+    Sheep,
+}
+
+impl FromStr for ScanProjection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "sheep" => Ok(Self::Sheep),
+            _ => Err(format!("unknown scan projection {s:?}")),
+        }
+    }
+}
+
+/// How a [DynamoDBSheepShed] runs the parallel scans of the whole table, used by
+/// [sheep_shed::AsyncSheepShed::sheep_stream] and, in [crate::SheepCountMode::Scan],
+/// [sheep_shed::AsyncSheepShed::sheep_count]
+///
+/// The default is one segment per 100k items of the table, at most
+/// [DEFAULT_MAX_CONCURRENT_SEGMENTS] segments at the same time, pages of 1MB,
+/// [ScanProjection::All] and eventually consistent reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanConfig {
+    segments: Option<u32>,
+    max_concurrent_segments: usize,
+    page_limit: Option<u32>,
+    projection: ScanProjection,
+    consistent_read: bool,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            segments: None,
+            max_concurrent_segments: DEFAULT_MAX_CONCURRENT_SEGMENTS,
+            page_limit: None,
+            projection: ScanProjection::All,
+            consistent_read: false,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Creates the default [ScanConfig] overridden by the environment variables
+    /// that are set:
+    /// - `SCAN_SEGMENTS`, see [ScanConfig::with_segments]
+    /// - `SCAN_MAX_CONCURRENT_SEGMENTS`, see [ScanConfig::with_max_concurrent_segments]
+    /// - `SCAN_PAGE_LIMIT`, see [ScanConfig::with_page_limit]
+    /// - `SCAN_PROJECTION`, `all` or `sheep`, see [ScanConfig::with_projection]
+    /// - `SCAN_CONSISTENT_READ`, `true` or `false`, see [ScanConfig::with_consistent_read]
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if one of
+    /// the environment variables is set to an invalid value.
+    pub fn from_env() -> Result<Self, sheep_shed::errors::Error> {
+        let config = Self::default();
+        let config = match env_var("SCAN_SEGMENTS", "a number of segments")? {
+            Some(segments @ 1..=1_000_000) => config.with_segments(segments),
+            Some(_) => Err(invalid("SCAN_SEGMENTS", "between 1 and 1 000 000"))?,
+            None => config,
+        };
+        let config = match env_var("SCAN_MAX_CONCURRENT_SEGMENTS", "a number of segments")? {
+            Some(0) => Err(invalid("SCAN_MAX_CONCURRENT_SEGMENTS", "positive"))?,
+            Some(max) => config.with_max_concurrent_segments(max),
+            None => config,
+        };
+        let config = match env_var("SCAN_PAGE_LIMIT", "a number of items")? {
+            Some(0) => Err(invalid("SCAN_PAGE_LIMIT", "positive"))?,
+            Some(limit) => config.with_page_limit(limit),
+            None => config,
+        };
+        let config = match env_var("SCAN_PROJECTION", "`all` or `sheep`")? {
+            Some(projection) => config.with_projection(projection),
+            None => config,
+        };
+        Ok(
+            match env_var("SCAN_CONSISTENT_READ", "`true` or `false`")? {
+                Some(consistent_read) => config.with_consistent_read(consistent_read),
+                None => config,
+            },
+        )
+    }
+
+    /// Divide the table into exactly `segments` segments instead of deriving
+    /// their number from the approximate item count of the table
+    /// # Panics
+    /// Panics if `segments` is 0 or more than the 1 000 000 DynamoDB accepts.
+    pub fn with_segments(mut self, segments: u32) -> Self {
+        assert!(
+            (1..=1_000_000).contains(&segments),
+            "a scan has between 1 and 1 000 000 segments"
+        );
+        self.segments = Some(segments);
+        self
+    }
+
+    /// Scan at most `max_concurrent_segments` segments at the same time, the
+    /// others wait for one of them to complete
+    /// # Panics
+    /// Panics if `max_concurrent_segments` is 0.
+    pub fn with_max_concurrent_segments(mut self, max_concurrent_segments: usize) -> Self {
+        assert!(max_concurrent_segments > 0, "at least one segment must run");
+        self.max_concurrent_segments = max_concurrent_segments;
+        self
+    }
+
+    /// Evaluate at most `page_limit` items per scan request, the `Limit` of DynamoDB
+    /// # Panics
+    /// Panics if `page_limit` is 0.
+    pub fn with_page_limit(mut self, page_limit: u32) -> Self {
+        assert!(page_limit > 0, "a scan page has at least one item");
+        self.page_limit = Some(page_limit);
+        self
+    }
+
+    /// Choose the attributes retrieved by the scans, counting scans retrieve none
+    pub fn with_projection(mut self, projection: ScanProjection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Use strongly consistent reads, which cost twice the read capacity
+    pub fn with_consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+}
+
+impl DynamoDBSheepShed {
+    /// Launch a parallel scan of the whole table and return a [Stream] of the
+    /// scan pages, yielded as soon as each segment retrieves them.
+    /// The pages only contain the items of the living [sheep_shed::Sheep]s of the
+    /// shed, not their [sheep_shed::WeighIn]s nor the tombstones.
+    ///
+    /// One task is spawned per segment but at most `max_concurrent_segments` of
+    /// them scan at the same time (see [ScanConfig]). Each task stops as soon as
+    /// the [Stream] is dropped. Each page is retried according to the
+    /// [crate::RetryConfig], a segment that still fails yields its error in the
+    /// [Stream] and stops.
+    pub(crate) async fn _full_table_scan(
+        &self,
+        count_only: bool,
+    ) -> Result<
+        impl Stream<Item = Result<ScanOutput, sheep_shed::errors::Error>> + Send + 'static,
+        sheep_shed::errors::Error,
+    > {
+        log::info!(
+            "_full_table_scan(count_only={count_only}) with {:?}",
+            self.scan_config
+        );
+        let parallel_scan_threads = match self.scan_config.segments {
+            Some(segments) => segments as i32,
+            None => {
+                // Request the approximate item count that DynamoDB updates sometimes
+                let describe_table = self
+                    .client
+                    .describe_table()
+                    .table_name(self.table_name.as_str());
+                let approx_table_size = self
+                    .retry_config
+                    .call("describe_table", || describe_table.clone().send())
+                    .await
+                    .map_err(database_error)?
+                    .table
+                    .expect("table must exist")
+                    .item_count
+                    .unwrap_or_default();
+
+                log::info!("approx_table_size={approx_table_size}");
+
+                // The database representation of a Sheep is ~50 bytes
+                // Therefore, a scan page of 1MB will contain ~20 000 sheeps
+                // We will be very conservative and devide the table into 100k sheeps segments because
+                // it would be very counter-productive to have more parallel scans than we would have had pages.
+                // Bottom line, with 100k items per segment we expect each segment to be fully scanned in 5 requests.
+                min(1 + approx_table_size / 100_000, 1_000_000) as i32
+            }
+        };
+
+        // Each segment can have at most one page waiting to be consumed, so the
+        // memory footprint stays bounded whatever the size of the table.
+        let (sender, receiver) = mpsc::channel(0);
+        let semaphore = Arc::new(Semaphore::new(self.scan_config.max_concurrent_segments));
+        let handle = tokio::runtime::Handle::current();
+        for seg in 0..parallel_scan_threads {
+            let client = self.client.clone();
+            let table_name = self.table_name.clone();
+            let sheep_sort_key = sheep_sort_key(&self.shed_id);
+            let key_attribute = self.key_attribute.clone();
+            let scan_config = self.scan_config.clone();
+            let retry_config = self.retry_config.clone();
+            let semaphore = semaphore.clone();
+            let mut sender = sender.clone();
+            handle.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("never closed");
+                // The Stream may have been dropped while waiting for the permit
+                if sender.is_closed() {
+                    return;
+                }
+                let start = Instant::now();
+                let mut pages = 0;
+                let mut items = 0;
+                let mut exclusive_start_key = None;
+                loop {
+                    let scan = client
+                        .scan()
+                        .table_name(&table_name)
+                        .segment(seg)
+                        .total_segments(parallel_scan_threads)
+                        .set_exclusive_start_key(exclusive_start_key)
+                        .set_limit(scan_config.page_limit.map(|limit| limit as i32))
+                        .consistent_read(scan_config.consistent_read)
+                        // Only the living Sheep items of the shed, not their history
+                        .filter_expression(format!(
+                            "#sk = :sheep AND attribute_not_exists({KILLED_AT_ATTRIBUTE})"
+                        ))
+                        .expression_attribute_names("#sk", SORT_KEY_ATTRIBUTE)
+                        .expression_attribute_values(":sheep", sheep_sort_key.clone());
+                    let scan = match (count_only, scan_config.projection) {
+                        (true, _) => scan.select(Select::Count),
+                        (false, ScanProjection::All) => scan.select(Select::AllAttributes),
+                        (false, ScanProjection::Sheep) => {
+                            // The key attribute may be one of the attributes of the
+                            // Sheep, and DynamoDB refuses overlapping projections
+                            let attributes = SHEEP_ATTRIBUTES
+                                .into_iter()
+                                .filter(|attribute| *attribute != key_attribute)
+                                .collect::<Vec<_>>();
+                            attributes
+                                .iter()
+                                .enumerate()
+                                // Some of the attributes are reserved words (e.g. `name`)
+                                .fold(scan, |scan, (i, attribute)| {
+                                    scan.expression_attribute_names(format!("#p{i}"), *attribute)
+                                })
+                                .expression_attribute_names("#key", key_attribute.as_str())
+                                .select(Select::SpecificAttributes)
+                                .projection_expression(
+                                    std::iter::once("#key".to_owned())
+                                        .chain((0..attributes.len()).map(|i| format!("#p{i}")))
+                                        .collect::<Vec<_>>()
+                                        .join(", "),
+                                )
+                        }
+                    };
+                    // A page that keeps failing stops its segment only
+                    let result = retry_config
+                        .call("scan", || scan.clone().send())
+                        .await
+                        .map_err(database_error);
+                    match result {
+                        Ok(mut page) => {
+                            pages += 1;
+                            items += page.count;
+                            exclusive_start_key = page.last_evaluated_key.take();
+                            let is_last_page = exclusive_start_key.is_none();
+                            // If the receiver is gone, nobody cares about the next pages
+                            if sender.send(Ok(page)).await.is_err() || is_last_page {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            let _ = sender.send(Err(e)).await;
+                            break;
+                        }
+                    }
+                }
+                log::info!(
+                    "segment {seg}/{parallel_scan_threads}: {items} sheeps in {pages} pages, {:?}",
+                    start.elapsed()
+                );
+            });
+        }
+
+        log::info!("Launched {parallel_scan_threads} green-threads");
+        Ok(receiver)
+    }
+}
@@ -0,0 +1,916 @@
+//! The provisioning and the migrations of the table of the [crate::DynamoDBSheepShed]s,
+//! see [SheepTable]
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use aws_sdk_dynamodb::{
+    operation::{describe_table::DescribeTableError, update_item::UpdateItemError},
+    types::{
+        AttributeDefinition, AttributeValue, BillingMode, CreateGlobalSecondaryIndexAction,
+        GlobalSecondaryIndex, GlobalSecondaryIndexUpdate, IndexStatus, KeySchemaElement, KeyType,
+        Projection, ProjectionType, ScalarAttributeType, TableDescription, TableStatus,
+        TimeToLiveSpecification, TimeToLiveStatus,
+    },
+    Client,
+};
+use serde_dynamo::aws_sdk_dynamodb_1::{from_attribute_value, to_attribute_value};
+use sheep_shed::Weight;
+
+use crate::{
+    builder::env_var, database_error, shed_prime_weight_value, RetryConfig, DEFAULT_KEY_ATTRIBUTE,
+    EXPIRE_AT_ATTRIBUTE, KILLED_AT_ATTRIBUTE, KILLED_INDEX_NAME, SHED_KILLED_ATTRIBUTE,
+    SHED_PRIME_WEIGHT_ATTRIBUTE, SHEEP_COUNTER_SORT_KEY, SHEEP_COUNTER_TATTOO,
+    SHEEP_COUNT_ATTRIBUTE, SHEEP_SORT_KEY, SORT_KEY_ATTRIBUTE, WEIGHT_INDEX_NAME,
+};
+
+/// The [SORT_KEY_ATTRIBUTE] value of the item holding the schema version of the
+/// table, in the partition of the [SHEEP_COUNTER_TATTOO]. It has no shed prefix
+/// so it belongs to no shed.
+pub const SCHEMA_VERSION_SORT_KEY: &str = "schema_version";
+/// The attribute of the schema version item holding the version of the last
+/// [Migration] applied to the table
+pub const SCHEMA_VERSION_ATTRIBUTE: &str = "schema_version";
+/// The maximum time [SheepTable::ensure_table] waits for the table to become active
+const TABLE_ACTIVE_TIMEOUT: Duration = Duration::from_secs(300);
+/// The time between two checks of the status of the table
+const TABLE_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A global secondary index of the table, see [INDEXES]
+struct Index {
+    name: &'static str,
+    /// The name and the type of its hash key
+    hash: (&'static str, ScalarAttributeType),
+    /// The name and the type of its range key
+    range: (&'static str, ScalarAttributeType),
+    /// The change reported by [SheepTable::ensure_table] when it creates it
+    created: TableChange,
+}
+
+/// The global secondary indexes of the table, all projecting all the attributes
+const INDEXES: [Index; 2] = [
+    Index {
+        name: WEIGHT_INDEX_NAME,
+        hash: (SHED_PRIME_WEIGHT_ATTRIBUTE, ScalarAttributeType::S),
+        range: ("weight", ScalarAttributeType::N),
+        created: TableChange::WeightIndexCreated,
+    },
+    Index {
+        name: KILLED_INDEX_NAME,
+        hash: (SHED_KILLED_ATTRIBUTE, ScalarAttributeType::S),
+        range: (KILLED_AT_ATTRIBUTE, ScalarAttributeType::S),
+        created: TableChange::KilledIndexCreated,
+    },
+];
+
+/// A versioned migration of the items of the table, see [SheepTable::migrate]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    /// The schema version of the table once the migration is applied
+    pub version: u32,
+    /// What the migration does
+    pub description: &'static str,
+}
+
+/// All the [Migration]s, in the order they are applied
+pub const MIGRATIONS: [Migration; 3] = [
+    Migration {
+        version: 1,
+        description: "backfill the shed_prime_weight of the sheeps written before the weight index",
+    },
+    Migration {
+        version: 2,
+        description: "reconcile the sheep counter of every shed with its living sheeps",
+    },
+    Migration {
+        version: 3,
+        description: "backfill the shed_killed of the tombstones written before the killed index",
+    },
+];
+
+/// A change made by [SheepTable::ensure_table]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableChange {
+    /// The table did not exist and was created with its indexes
+    Created,
+    /// The [WEIGHT_INDEX_NAME] index was missing and is being created, DynamoDB
+    /// backfills it in the background
+    WeightIndexCreated,
+    /// The [KILLED_INDEX_NAME] index was missing and is being created, DynamoDB
+    /// backfills it in the background
+    KilledIndexCreated,
+    /// The TTL of the table was disabled and is now on the [EXPIRE_AT_ATTRIBUTE]
+    TimeToLiveEnabled,
+}
+
+/// The table holding the sheds of the [crate::DynamoDBSheepShed]s, to create it,
+/// check it and migrate its items
+///
+/// Its hash key is the key attribute (see
+/// [crate::DynamoDBSheepShedBuilder::with_key_attribute]), of type `N`, its sort key
+/// is the [SORT_KEY_ATTRIBUTE], of type `S`, it has the [WEIGHT_INDEX_NAME] and
+/// [KILLED_INDEX_NAME] global secondary indexes and its TTL attribute is the
+/// [EXPIRE_AT_ATTRIBUTE].
+#[derive(Debug, Clone)]
+pub struct SheepTable {
+    client: Client,
+    table_name: String,
+    key_attribute: String,
+    retry_config: RetryConfig,
+}
+
+impl SheepTable {
+    /// Creates a [SheepTable] for the table with the given name, whose hash key
+    /// is the [DEFAULT_KEY_ATTRIBUTE]
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            key_attribute: DEFAULT_KEY_ATTRIBUTE.to_owned(),
+            retry_config: RetryConfig::default(),
+        }
+    }
+
+    /// Creates a [SheepTable] configured by the same environment variables as
+    /// [crate::DynamoDBSheepShedBuilder::from_env]: `BACKEND_TABLE_NAME`,
+    /// `BACKEND_TABLE_KEY_ATTRIBUTE` and `RETRY_*`
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if
+    /// `BACKEND_TABLE_NAME` is not set or if a variable has an invalid value.
+    pub fn from_env(client: Client) -> Result<Self, sheep_shed::errors::Error> {
+        let table_name =
+            env_var::<String>("BACKEND_TABLE_NAME", "a table name")?.ok_or_else(|| {
+                sheep_shed::errors::Error::InvalidConfiguration(
+                    "Mandatory environment variable `BACKEND_TABLE_NAME` is not set".to_owned(),
+                )
+            })?;
+        let table = Self::new(client, table_name).with_retry_config(RetryConfig::from_env()?);
+        Ok(
+            match env_var::<String>("BACKEND_TABLE_KEY_ATTRIBUTE", "an attribute name")? {
+                Some(key_attribute) => table.with_key_attribute(key_attribute),
+                None => table,
+            },
+        )
+    }
+
+    /// The name of the hash key of the table, of type `N`
+    pub fn with_key_attribute(mut self, key_attribute: impl Into<String>) -> Self {
+        self.key_attribute = key_attribute.into();
+        self
+    }
+
+    /// See [crate::DynamoDBSheepShed::with_retry_config]
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// The name of the table
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Create the table if it does not exist, or check its keys if it does, then
+    /// enable its TTL and create its [WEIGHT_INDEX_NAME] and [KILLED_INDEX_NAME]
+    /// indexes if they are missing. Calling it again on a table that is up to date
+    /// changes nothing.
+    ///
+    /// It waits for a new table to become active, but not for the backfill of the
+    /// last index it creates on an existing table: until it is over, the queries of
+    /// the index (e.g. [sheep_shed::AsyncSheepShed::heaviest_matching]) report an
+    /// [sheep_shed::errors::Error::IndexNotAvailable]. As DynamoDB backfills one
+    /// new index at a time, it does wait for the backfill of an index before
+    /// creating the next one.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the keys,
+    /// the indexes or the TTL attribute of the existing table are not the expected
+    /// ones, or an [sheep_shed::errors::Error::Transient] if a backfill it waits for
+    /// takes too long, it can then simply be called again.
+    pub async fn ensure_table(&self) -> Result<Vec<TableChange>, sheep_shed::errors::Error> {
+        log::info!(
+            "ensure_table(table_name={}, key_attribute={})",
+            self.table_name,
+            self.key_attribute
+        );
+        let mut changes = vec![];
+        let table = match self._describe_table().await? {
+            Some(table) => {
+                self.check_keys(&table)?;
+                table
+            }
+            None => {
+                self._create_table().await?;
+                changes.push(TableChange::Created);
+                self._wait_until_active().await?
+            }
+        };
+
+        if self._ensure_time_to_live().await? {
+            changes.push(TableChange::TimeToLiveEnabled);
+        }
+
+        let mut index_created = false;
+        for index in &INDEXES {
+            match table
+                .global_secondary_indexes()
+                .iter()
+                .find(|existing| existing.index_name() == Some(index.name))
+            {
+                Some(existing) => check_key_schema(
+                    index.name,
+                    existing.key_schema(),
+                    index.hash.0,
+                    index.range.0,
+                )?,
+                None => {
+                    if index_created {
+                        self._wait_until_indexes_active().await?;
+                    }
+                    self._create_index(index).await?;
+                    changes.push(index.created);
+                    index_created = true;
+                }
+            }
+        }
+
+        log::info!("changes={changes:?}");
+        Ok(changes)
+    }
+
+    /// Apply, in order, the [MIGRATIONS] the table has not been through yet and
+    /// return them. The schema version of the table is recorded after each one, and
+    /// each is idempotent, so an interrupted migration can simply be run again.
+    pub async fn migrate(&self) -> Result<Vec<Migration>, sheep_shed::errors::Error> {
+        let current = self.schema_version().await?;
+        log::info!(
+            "migrate(table_name={}) from version {current}",
+            self.table_name
+        );
+        let mut applied = vec![];
+        for migration in MIGRATIONS.into_iter().filter(|m| m.version > current) {
+            log::info!(
+                "applying migration {}: {}",
+                migration.version,
+                migration.description
+            );
+            match migration.version {
+                1 => self._backfill_shed_prime_weight().await?,
+                2 => self._reconcile_sheep_counters().await?,
+                3 => self._backfill_shed_killed().await?,
+                version => unreachable!("no migration to version {version}"),
+            }
+            self._set_schema_version(migration.version).await?;
+            applied.push(migration);
+        }
+        log::info!("{} migrations applied", applied.len());
+        Ok(applied)
+    }
+
+    /// The version of the last [Migration] applied to the table, 0 if none is
+    pub async fn schema_version(&self) -> Result<u32, sheep_shed::errors::Error> {
+        let get_item = self
+            .client
+            .get_item()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.schema_version_key()))
+            .consistent_read(true);
+        Ok(self
+            .retry_config
+            .call("get_item", || get_item.clone().send())
+            .await
+            .map_err(database_error)?
+            .item
+            .and_then(|mut item| item.remove(SCHEMA_VERSION_ATTRIBUTE))
+            .map(|version| from_attribute_value(version).expect("written by migrate"))
+            .unwrap_or_default())
+    }
+
+    /// The DynamoDB key of the schema version item
+    fn schema_version_key(&self) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            (
+                self.key_attribute.clone(),
+                to_attribute_value(SHEEP_COUNTER_TATTOO).expect("cannot fail"),
+            ),
+            (
+                SORT_KEY_ATTRIBUTE.to_owned(),
+                AttributeValue::S(SCHEMA_VERSION_SORT_KEY.to_owned()),
+            ),
+        ])
+    }
+
+    /// Check the key attribute and the [SORT_KEY_ATTRIBUTE] of an existing table
+    fn check_keys(&self, table: &TableDescription) -> Result<(), sheep_shed::errors::Error> {
+        check_key_schema(
+            &self.table_name,
+            table.key_schema(),
+            &self.key_attribute,
+            SORT_KEY_ATTRIBUTE,
+        )?;
+        for (name, expected) in [
+            (self.key_attribute.as_str(), ScalarAttributeType::N),
+            (SORT_KEY_ATTRIBUTE, ScalarAttributeType::S),
+        ] {
+            let attribute_type = table
+                .attribute_definitions()
+                .iter()
+                .find(|definition| definition.attribute_name() == name)
+                .map(|definition| definition.attribute_type());
+            if attribute_type != Some(&expected) {
+                return Err(sheep_shed::errors::Error::InvalidConfiguration(format!(
+                    "the `{name}` attribute of {} is of type {attribute_type:?}, expected {expected}",
+                    self.table_name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe the table, [None] if it does not exist
+    async fn _describe_table(&self) -> Result<Option<TableDescription>, sheep_shed::errors::Error> {
+        let describe_table = self
+            .client
+            .describe_table()
+            .table_name(self.table_name.as_str());
+        match self
+            .retry_config
+            .call("describe_table", || describe_table.clone().send())
+            .await
+        {
+            Ok(output) => Ok(output.table),
+            Err(e) => match e.as_service_error() {
+                Some(DescribeTableError::ResourceNotFoundException(_)) => Ok(None),
+                _ => Err(database_error(e)),
+            },
+        }
+    }
+
+    async fn _create_table(&self) -> Result<(), sheep_shed::errors::Error> {
+        log::info!("creating table {}", self.table_name);
+        let create_table = self
+            .client
+            .create_table()
+            .table_name(self.table_name.as_str())
+            .billing_mode(BillingMode::PayPerRequest)
+            .set_attribute_definitions(Some(
+                self.key_attribute_definitions()
+                    .into_iter()
+                    .chain(INDEXES.iter().flat_map(Index::attribute_definitions))
+                    .collect(),
+            ))
+            .key_schema(key_schema_element(&self.key_attribute, KeyType::Hash))
+            .key_schema(key_schema_element(SORT_KEY_ATTRIBUTE, KeyType::Range))
+            .set_global_secondary_indexes(Some(
+                INDEXES
+                    .iter()
+                    .map(|index| {
+                        GlobalSecondaryIndex::builder()
+                            .index_name(index.name)
+                            .set_key_schema(Some(index.key_schema()))
+                            .projection(index_projection())
+                            .build()
+                            .expect("all the fields are set")
+                    })
+                    .collect(),
+            ));
+        self.retry_config
+            .call("create_table", || create_table.clone().send())
+            .await
+            .map_err(database_error)?;
+        Ok(())
+    }
+
+    /// The definitions of the keys of the table
+    fn key_attribute_definitions(&self) -> Vec<AttributeDefinition> {
+        vec![
+            attribute_definition(&self.key_attribute, ScalarAttributeType::N),
+            attribute_definition(SORT_KEY_ATTRIBUTE, ScalarAttributeType::S),
+        ]
+    }
+
+    /// Wait for the table to become active and return its description
+    async fn _wait_until_active(&self) -> Result<TableDescription, sheep_shed::errors::Error> {
+        let start = tokio::time::Instant::now();
+        loop {
+            match self._describe_table().await? {
+                Some(table) if table.table_status() == Some(&TableStatus::Active) => {
+                    log::info!(
+                        "table {} active after {:?}",
+                        self.table_name,
+                        start.elapsed()
+                    );
+                    return Ok(table);
+                }
+                _ if start.elapsed() > TABLE_ACTIVE_TIMEOUT => {
+                    return Err(sheep_shed::errors::Error::Transient(format!(
+                        "table {} not active after {TABLE_ACTIVE_TIMEOUT:?}",
+                        self.table_name
+                    )));
+                }
+                _ => tokio::time::sleep(TABLE_STATUS_POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Wait for the backfill of the new indexes of the table to be over
+    async fn _wait_until_indexes_active(&self) -> Result<(), sheep_shed::errors::Error> {
+        let start = tokio::time::Instant::now();
+        loop {
+            let active = self._describe_table().await?.is_some_and(|table| {
+                table
+                    .global_secondary_indexes()
+                    .iter()
+                    .all(|index| index.index_status() == Some(&IndexStatus::Active))
+            });
+            if active {
+                log::info!(
+                    "indexes of {} active after {:?}",
+                    self.table_name,
+                    start.elapsed()
+                );
+                return Ok(());
+            }
+            if start.elapsed() > TABLE_ACTIVE_TIMEOUT {
+                return Err(sheep_shed::errors::Error::Transient(format!(
+                    "indexes of {} not active after {TABLE_ACTIVE_TIMEOUT:?}",
+                    self.table_name
+                )));
+            }
+            tokio::time::sleep(TABLE_STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Enable the TTL on the [EXPIRE_AT_ATTRIBUTE], return `true` if it was disabled
+    async fn _ensure_time_to_live(&self) -> Result<bool, sheep_shed::errors::Error> {
+        let describe_time_to_live = self
+            .client
+            .describe_time_to_live()
+            .table_name(self.table_name.as_str());
+        let description = self
+            .retry_config
+            .call("describe_time_to_live", || {
+                describe_time_to_live.clone().send()
+            })
+            .await
+            .map_err(database_error)?
+            .time_to_live_description;
+        let (status, attribute_name) = description
+            .map(|d| (d.time_to_live_status, d.attribute_name))
+            .unwrap_or_default();
+        match status {
+            Some(TimeToLiveStatus::Enabled | TimeToLiveStatus::Enabling)
+                if attribute_name.as_deref() == Some(EXPIRE_AT_ATTRIBUTE) =>
+            {
+                Ok(false)
+            }
+            Some(TimeToLiveStatus::Enabled | TimeToLiveStatus::Enabling) => {
+                Err(sheep_shed::errors::Error::InvalidConfiguration(format!(
+                    "the TTL attribute of {} is {attribute_name:?}, expected {EXPIRE_AT_ATTRIBUTE}",
+                    self.table_name
+                )))
+            }
+            _ => {
+                log::info!("enabling the TTL of {}", self.table_name);
+                let update_time_to_live = self
+                    .client
+                    .update_time_to_live()
+                    .table_name(self.table_name.as_str())
+                    .time_to_live_specification(
+                        TimeToLiveSpecification::builder()
+                            .enabled(true)
+                            .attribute_name(EXPIRE_AT_ATTRIBUTE)
+                            .build()
+                            .expect("all the fields are set"),
+                    );
+                self.retry_config
+                    .call("update_time_to_live", || update_time_to_live.clone().send())
+                    .await
+                    .map_err(database_error)?;
+                Ok(true)
+            }
+        }
+    }
+
+    async fn _create_index(&self, index: &Index) -> Result<(), sheep_shed::errors::Error> {
+        log::info!("creating index {} of {}", index.name, self.table_name);
+        let update_table = self
+            .client
+            .update_table()
+            .table_name(self.table_name.as_str())
+            // The definitions of the keys of the table are kept as they are
+            .set_attribute_definitions(Some(
+                self.key_attribute_definitions()
+                    .into_iter()
+                    .chain(index.attribute_definitions())
+                    .collect(),
+            ))
+            .global_secondary_index_updates(
+                GlobalSecondaryIndexUpdate::builder()
+                    .create(
+                        CreateGlobalSecondaryIndexAction::builder()
+                            .index_name(index.name)
+                            .set_key_schema(Some(index.key_schema()))
+                            .projection(index_projection())
+                            .build()
+                            .expect("all the fields are set"),
+                    )
+                    .build(),
+            );
+        self.retry_config
+            .call("update_table", || update_table.clone().send())
+            .await
+            .map_err(database_error)?;
+        Ok(())
+    }
+
+    /// Migration 1: give their [SHED_PRIME_WEIGHT_ATTRIBUTE] to the items of the
+    /// living [sheep_shed::Sheep]s written without it, so they appear in the
+    /// [WEIGHT_INDEX_NAME] index. An item whose weight changed in the meantime
+    /// already has it and is left alone.
+    async fn _backfill_shed_prime_weight(&self) -> Result<(), sheep_shed::errors::Error> {
+        let sheep_sort_key_suffix = format!("#{SHEEP_SORT_KEY}");
+        let mut backfilled = 0;
+        let mut exclusive_start_key = None;
+        loop {
+            let scan = self
+                .client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .set_exclusive_start_key(exclusive_start_key)
+                .filter_expression(
+                    "attribute_exists(weight) AND attribute_not_exists(#shed_prime_weight) \
+                    AND attribute_not_exists(#killed_at)",
+                )
+                .projection_expression("#key, #sk, weight")
+                .expression_attribute_names("#key", self.key_attribute.as_str())
+                .expression_attribute_names("#sk", SORT_KEY_ATTRIBUTE)
+                .expression_attribute_names("#shed_prime_weight", SHED_PRIME_WEIGHT_ATTRIBUTE)
+                .expression_attribute_names("#killed_at", KILLED_AT_ATTRIBUTE);
+            let page = self
+                .retry_config
+                .call("scan", || scan.clone().send())
+                .await
+                .map_err(database_error)?;
+            for mut item in page.items.unwrap_or_default() {
+                // Only the items of the Sheeps of a shed, not their WeighIns
+                let Some(shed_id) = item
+                    .get(SORT_KEY_ATTRIBUTE)
+                    .and_then(|sk| sk.as_s().ok())
+                    .and_then(|sk| sk.strip_suffix(&sheep_sort_key_suffix))
+                    .map(str::to_owned)
+                else {
+                    continue;
+                };
+                let weight = item.remove("weight").expect("filtered on it");
+                let parsed_weight: Weight = from_attribute_value(weight.clone()).map_err(|e| {
+                    sheep_shed::errors::Error::GenericError(format!(
+                        "invalid weight in {item:?}: {e}"
+                    ))
+                })?;
+                let shed_prime_weight = shed_prime_weight_value(&shed_id, &parsed_weight);
+                let update_item = self
+                    .client
+                    .update_item()
+                    .table_name(self.table_name.as_str())
+                    .set_key(Some(item))
+                    .update_expression("SET #shed_prime_weight = :shed_prime_weight")
+                    .condition_expression(
+                        "weight = :weight AND attribute_not_exists(#shed_prime_weight) \
+                        AND attribute_not_exists(#killed_at)",
+                    )
+                    .expression_attribute_names("#shed_prime_weight", SHED_PRIME_WEIGHT_ATTRIBUTE)
+                    .expression_attribute_names("#killed_at", KILLED_AT_ATTRIBUTE)
+                    .expression_attribute_values(":shed_prime_weight", shed_prime_weight)
+                    .expression_attribute_values(":weight", weight);
+                match self
+                    .retry_config
+                    .call("update_item", || update_item.clone().send())
+                    .await
+                {
+                    Ok(_) => backfilled += 1,
+                    Err(e) => match e.as_service_error() {
+                        // Modified, killed or already backfilled since the scan
+                        Some(UpdateItemError::ConditionalCheckFailedException(_)) => (),
+                        _ => return Err(database_error(e)),
+                    },
+                }
+            }
+            exclusive_start_key = page.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+        log::info!("{backfilled} sheeps backfilled");
+        Ok(())
+    }
+
+    /// Migration 2: set the counter of every shed (see [crate::SHEEP_COUNTER_SORT_KEY])
+    /// to the number of its living [sheep_shed::Sheep]s, found by a scan of the
+    /// whole table, so the sheds filled before the counters existed count right.
+    ///
+    /// Like [crate::DynamoDBSheepShed::reconcile_sheep_count], a counter is only
+    /// overwritten if it did not change during the scan.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::GenericError] if a counter
+    /// changed during the scan, the migration can then simply be run again.
+    async fn _reconcile_sheep_counters(&self) -> Result<(), sheep_shed::errors::Error> {
+        let sheep_sort_key_suffix = format!("#{SHEEP_SORT_KEY}");
+        let counter_sort_key_suffix = format!("#{SHEEP_COUNTER_SORT_KEY}");
+        // The counters as they are before the scan, by shed
+        let mut counters = HashMap::<String, (AttributeValue, i64)>::new();
+        let mut exclusive_start_key = None;
+        loop {
+            let query = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .key_condition_expression("#key = :counter_tattoo")
+                .expression_attribute_names("#key", self.key_attribute.as_str())
+                .expression_attribute_values(
+                    ":counter_tattoo",
+                    to_attribute_value(SHEEP_COUNTER_TATTOO).expect("cannot fail"),
+                )
+                .consistent_read(true)
+                .set_exclusive_start_key(exclusive_start_key);
+            let page = self
+                .retry_config
+                .call("query", || query.clone().send())
+                .await
+                .map_err(database_error)?;
+            for mut item in page.items.unwrap_or_default() {
+                let Some(shed_id) = item
+                    .get(SORT_KEY_ATTRIBUTE)
+                    .and_then(|sk| sk.as_s().ok())
+                    .and_then(|sk| sk.strip_suffix(&counter_sort_key_suffix))
+                    .map(str::to_owned)
+                else {
+                    continue;
+                };
+                let Some(count) = item.remove(SHEEP_COUNT_ATTRIBUTE) else {
+                    continue;
+                };
+                let parsed = from_attribute_value::<i64>(count.clone()).map_err(|e| {
+                    sheep_shed::errors::Error::GenericError(format!(
+                        "invalid counter for shed {shed_id}: {e}"
+                    ))
+                })?;
+                counters.insert(shed_id, (count, parsed));
+            }
+            exclusive_start_key = page.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        // The living Sheeps, by shed
+        let mut scanned = HashMap::<String, usize>::new();
+        let mut exclusive_start_key = None;
+        loop {
+            let scan = self
+                .client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .set_exclusive_start_key(exclusive_start_key)
+                .filter_expression("attribute_not_exists(#killed_at)")
+                .projection_expression("#sk")
+                .expression_attribute_names("#sk", SORT_KEY_ATTRIBUTE)
+                .expression_attribute_names("#killed_at", KILLED_AT_ATTRIBUTE)
+                .consistent_read(true);
+            let page = self
+                .retry_config
+                .call("scan", || scan.clone().send())
+                .await
+                .map_err(database_error)?;
+            for item in page.items.unwrap_or_default() {
+                if let Some(shed_id) = item
+                    .get(SORT_KEY_ATTRIBUTE)
+                    .and_then(|sk| sk.as_s().ok())
+                    .and_then(|sk| sk.strip_suffix(&sheep_sort_key_suffix))
+                {
+                    *scanned.entry(shed_id.to_owned()).or_default() += 1;
+                }
+            }
+            exclusive_start_key = page.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        let mut reconciled = 0;
+        let shed_ids = counters
+            .keys()
+            .chain(scanned.keys())
+            .cloned()
+            .collect::<HashSet<_>>();
+        for shed_id in shed_ids {
+            let count = scanned.get(&shed_id).copied().unwrap_or_default();
+            let counter = counters.remove(&shed_id);
+            if counter
+                .as_ref()
+                .is_some_and(|(_, counter)| *counter == count as i64)
+            {
+                continue;
+            }
+            let update_item = self
+                .client
+                .update_item()
+                .table_name(self.table_name.as_str())
+                .key(
+                    self.key_attribute.as_str(),
+                    to_attribute_value(SHEEP_COUNTER_TATTOO).expect("cannot fail"),
+                )
+                .key(
+                    SORT_KEY_ATTRIBUTE,
+                    AttributeValue::S(format!("{shed_id}{counter_sort_key_suffix}")),
+                )
+                .update_expression("SET #count = :scanned")
+                .expression_attribute_names("#count", SHEEP_COUNT_ATTRIBUTE)
+                .expression_attribute_values(":scanned", AttributeValue::N(count.to_string()));
+            let update_item = match counter {
+                Some((counter, _)) => update_item
+                    .condition_expression("#count = :counter")
+                    .expression_attribute_values(":counter", counter),
+                None => update_item.condition_expression("attribute_not_exists(#count)"),
+            };
+            self.retry_config
+                .call("update_item", || update_item.clone().send())
+                .await
+                .map_err(|e| match e.as_service_error() {
+                    Some(UpdateItemError::ConditionalCheckFailedException(_)) => {
+                        let err_string =
+                            format!("the counter of shed {shed_id} changed during the migration");
+                        log::error!("{err_string}");
+                        sheep_shed::errors::Error::GenericError(err_string)
+                    }
+                    _ => database_error(e),
+                })?;
+            log::info!("the counter of shed {shed_id} is now {count}");
+            reconciled += 1;
+        }
+        log::info!("{reconciled} counters reconciled");
+        Ok(())
+    }
+
+    /// Migration 3: give their [SHED_KILLED_ATTRIBUTE] to the tombstones written
+    /// without it, so they appear in the [KILLED_INDEX_NAME] index. A tombstone
+    /// resurrected in the meantime is left alone.
+    async fn _backfill_shed_killed(&self) -> Result<(), sheep_shed::errors::Error> {
+        let sheep_sort_key_suffix = format!("#{SHEEP_SORT_KEY}");
+        let mut backfilled = 0;
+        let mut exclusive_start_key = None;
+        loop {
+            let scan = self
+                .client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .set_exclusive_start_key(exclusive_start_key)
+                .filter_expression(
+                    "attribute_exists(#killed_at) AND attribute_not_exists(#shed_killed)",
+                )
+                .projection_expression("#key, #sk")
+                .expression_attribute_names("#key", self.key_attribute.as_str())
+                .expression_attribute_names("#sk", SORT_KEY_ATTRIBUTE)
+                .expression_attribute_names("#killed_at", KILLED_AT_ATTRIBUTE)
+                .expression_attribute_names("#shed_killed", SHED_KILLED_ATTRIBUTE);
+            let page = self
+                .retry_config
+                .call("scan", || scan.clone().send())
+                .await
+                .map_err(database_error)?;
+            for item in page.items.unwrap_or_default() {
+                // Only the tombstones of the Sheeps of a shed
+                let Some(shed_id) = item
+                    .get(SORT_KEY_ATTRIBUTE)
+                    .and_then(|sk| sk.as_s().ok())
+                    .and_then(|sk| sk.strip_suffix(&sheep_sort_key_suffix))
+                    .map(str::to_owned)
+                else {
+                    continue;
+                };
+                let update_item = self
+                    .client
+                    .update_item()
+                    .table_name(self.table_name.as_str())
+                    .set_key(Some(item))
+                    .update_expression("SET #shed_killed = :shed_killed")
+                    .condition_expression(
+                        "attribute_exists(#killed_at) AND attribute_not_exists(#shed_killed)",
+                    )
+                    .expression_attribute_names("#shed_killed", SHED_KILLED_ATTRIBUTE)
+                    .expression_attribute_names("#killed_at", KILLED_AT_ATTRIBUTE)
+                    .expression_attribute_values(":shed_killed", AttributeValue::S(shed_id));
+                match self
+                    .retry_config
+                    .call("update_item", || update_item.clone().send())
+                    .await
+                {
+                    Ok(_) => backfilled += 1,
+                    Err(e) => match e.as_service_error() {
+                        // Resurrected or already backfilled since the scan
+                        Some(UpdateItemError::ConditionalCheckFailedException(_)) => (),
+                        _ => return Err(database_error(e)),
+                    },
+                }
+            }
+            exclusive_start_key = page.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+        log::info!("{backfilled} tombstones backfilled");
+        Ok(())
+    }
+
+    /// Record the schema version of the table, never moving it backward
+    async fn _set_schema_version(&self, version: u32) -> Result<(), sheep_shed::errors::Error> {
+        let update_item = self
+            .client
+            .update_item()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.schema_version_key()))
+            .update_expression("SET #version = :version")
+            .condition_expression("attribute_not_exists(#version) OR #version < :version")
+            .expression_attribute_names("#version", SCHEMA_VERSION_ATTRIBUTE)
+            .expression_attribute_values(
+                ":version",
+                to_attribute_value(version).expect("cannot fail"),
+            );
+        match self
+            .retry_config
+            .call("update_item", || update_item.clone().send())
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => match e.as_service_error() {
+                // Another migration got there first
+                Some(UpdateItemError::ConditionalCheckFailedException(_)) => Ok(()),
+                _ => Err(database_error(e)),
+            },
+        }
+    }
+}
+
+/// Check that the `key_schema` of `what` is made of the `hash` and `range` keys
+fn check_key_schema(
+    what: &str,
+    key_schema: &[KeySchemaElement],
+    hash: &str,
+    range: &str,
+) -> Result<(), sheep_shed::errors::Error> {
+    let key = |key_type: KeyType| {
+        key_schema
+            .iter()
+            .find(|element| element.key_type() == &key_type)
+            .map(|element| element.attribute_name())
+    };
+    if key(KeyType::Hash) == Some(hash) && key(KeyType::Range) == Some(range) {
+        Ok(())
+    } else {
+        Err(sheep_shed::errors::Error::InvalidConfiguration(format!(
+            "the keys of {what} are {:?}/{:?}, expected {hash}/{range}",
+            key(KeyType::Hash),
+            key(KeyType::Range)
+        )))
+    }
+}
+
+fn attribute_definition(name: &str, attribute_type: ScalarAttributeType) -> AttributeDefinition {
+    AttributeDefinition::builder()
+        .attribute_name(name)
+        .attribute_type(attribute_type)
+        .build()
+        .expect("all the fields are set")
+}
+
+fn key_schema_element(name: &str, key_type: KeyType) -> KeySchemaElement {
+    KeySchemaElement::builder()
+        .attribute_name(name)
+        .key_type(key_type)
+        .build()
+        .expect("all the fields are set")
+}
+
+impl Index {
+    /// The definitions of its hash and range keys
+    fn attribute_definitions(&self) -> [AttributeDefinition; 2] {
+        [
+            attribute_definition(self.hash.0, self.hash.1.clone()),
+            attribute_definition(self.range.0, self.range.1.clone()),
+        ]
+    }
+
+    fn key_schema(&self) -> Vec<KeySchemaElement> {
+        vec![
+            key_schema_element(self.hash.0, KeyType::Hash),
+            key_schema_element(self.range.0, KeyType::Range),
+        ]
+    }
+}
+
+fn index_projection() -> Projection {
+    Projection::builder()
+        .projection_type(ProjectionType::All)
+        .build()
+}
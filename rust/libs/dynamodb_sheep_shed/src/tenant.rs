@@ -0,0 +1,175 @@
+use aws_sdk_dynamodb::Client;
+use futures::Stream;
+use sheep_shed::{
+    AddSheepOutcome, AsyncSheepShed, Cursor, ShedId, Sheep, SheepPage, Tattoo, TenantId, WeighIn,
+    Weight, WeightCriteria,
+};
+
+use crate::{DynamoDBSheepShed, KilledPage};
+
+/// A [DynamoDBSheepShed] restricted to the sheds of one tenant.
+///
+/// Its shed is the [ShedId] scoped to the [TenantId] (see [ShedId::scoped_to]) and
+/// the inner [DynamoDBSheepShed] cannot be reached, so the owner of a
+/// [TenantSheepShed] can never count, list, weigh or kill the [Sheep]s of another
+/// tenant nor of a public shed, even when they share a [ShedId].
+#[derive(Debug)]
+pub struct TenantSheepShed {
+    tenant_id: TenantId,
+    sheep_shed: DynamoDBSheepShed,
+}
+
+impl TenantSheepShed {
+    /// Creates a new [TenantSheepShed] for the shed with the given [ShedId] among
+    /// the sheds of `tenant_id`, configured as [DynamoDBSheepShed::new] does.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the
+    /// environment variables are missing or invalid.
+    pub fn new(
+        client: Client,
+        tenant_id: TenantId,
+        shed_id: ShedId,
+    ) -> Result<Self, sheep_shed::errors::Error> {
+        log::info!("tenant_id={tenant_id}");
+        let sheep_shed = DynamoDBSheepShed::new(client, shed_id.scoped_to(&tenant_id))?;
+        Ok(Self {
+            tenant_id,
+            sheep_shed,
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn local_new(
+        client: Client,
+        table_name: String,
+        tenant_id: TenantId,
+        shed_id: ShedId,
+    ) -> Self {
+        let sheep_shed =
+            DynamoDBSheepShed::local_new(client, table_name, shed_id.scoped_to(&tenant_id));
+        Self {
+            tenant_id,
+            sheep_shed,
+        }
+    }
+
+    /// The [TenantId] owning the shed
+    pub fn tenant_id(&self) -> &TenantId {
+        &self.tenant_id
+    }
+
+    /// See [DynamoDBSheepShed::list_killed]
+    pub async fn list_killed(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<KilledPage, sheep_shed::errors::Error> {
+        self.sheep_shed.list_killed(cursor, limit).await
+    }
+
+    /// See [DynamoDBSheepShed::resurrect_sheep]
+    pub async fn resurrect_sheep(
+        &mut self,
+        tattoo: &Tattoo,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        self.sheep_shed.resurrect_sheep(tattoo).await
+    }
+}
+
+impl AsyncSheepShed for TenantSheepShed {
+    async fn add_sheep(&mut self, sheep: Sheep) -> Result<(), sheep_shed::errors::Error> {
+        self.sheep_shed.add_sheep(sheep).await
+    }
+
+    async fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> Result<Vec<(Tattoo, AddSheepOutcome)>, sheep_shed::errors::Error> {
+        self.sheep_shed.add_sheep_batch(sheeps).await
+    }
+
+    async fn sheep_count(&self) -> Result<usize, sheep_shed::errors::Error> {
+        self.sheep_shed.sheep_count().await
+    }
+
+    async fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        self.sheep_shed.get_sheep(tattoo).await
+    }
+
+    async fn sheep_iter(
+        &self,
+    ) -> Result<impl Iterator<Item = Sheep> + Send, sheep_shed::errors::Error> {
+        self.sheep_shed.sheep_iter().await
+    }
+
+    fn sheep_stream(
+        &self,
+    ) -> impl Stream<Item = Result<Sheep, sheep_shed::errors::Error>> + Send + '_ {
+        self.sheep_shed.sheep_stream()
+    }
+
+    async fn list_page(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<SheepPage, sheep_shed::errors::Error> {
+        self.sheep_shed.list_page(cursor, limit).await
+    }
+
+    async fn heaviest_matching(
+        &self,
+        criteria: WeightCriteria,
+    ) -> Result<Option<Sheep>, sheep_shed::errors::Error> {
+        self.sheep_shed.heaviest_matching(criteria).await
+    }
+
+    async fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, sheep_shed::errors::Error> {
+        self.sheep_shed.kill_sheep(tattoo).await
+    }
+
+    async fn kill_sheep_if_unchanged(
+        &mut self,
+        sheep: &Sheep,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        self.sheep_shed.kill_sheep_if_unchanged(sheep).await
+    }
+
+    async fn update_weight(
+        &mut self,
+        tattoo: &Tattoo,
+        weight: Weight,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        self.sheep_shed.update_weight(tattoo, weight).await
+    }
+
+    async fn weight_history(
+        &self,
+        tattoo: &Tattoo,
+    ) -> Result<Vec<WeighIn>, sheep_shed::errors::Error> {
+        self.sheep_shed.weight_history(tattoo).await
+    }
+
+    /// Only between two sheds of the same tenant, see [TenantSheepShed]
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::ForeignTenant] if `to` belongs
+    /// to another tenant.
+    async fn transfer_sheep(
+        &mut self,
+        to: &mut Self,
+        tattoo: &Tattoo,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        if self.tenant_id != to.tenant_id {
+            log::error!(
+                "cannot transfer from tenant {} to tenant {}",
+                self.tenant_id,
+                to.tenant_id
+            );
+            return Err(sheep_shed::errors::Error::ForeignTenant(
+                self.tenant_id.clone(),
+            ));
+        }
+        self.sheep_shed
+            .transfer_sheep(&mut to.sheep_shed, tattoo)
+            .await
+    }
+}
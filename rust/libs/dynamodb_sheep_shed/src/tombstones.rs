@@ -0,0 +1,368 @@
+//! The tombstone mode of the [DynamoDBSheepShed], see [DynamoDBSheepShed::with_tombstones]
+
+use std::{collections::HashMap, time::Duration};
+
+use aws_sdk_dynamodb::types::{
+    AttributeValue, PutRequest, ReturnValuesOnConditionCheckFailure, TransactWriteItem, Update,
+    WriteRequest,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_dynamo::{aws_sdk_dynamodb_1::from_attribute_value, to_attribute_value};
+use sheep_shed::{Cursor, Sheep, Tattoo};
+
+use crate::{
+    condition_failure, database_error, decode_item, index_query_error, is_tombstone,
+    shed_prime_weight_value, sheep_sort_key, version_condition, DynamoDBSheepShed,
+    EXPIRE_AT_ATTRIBUTE, KILLED_AT_ATTRIBUTE, KILLED_BY_ATTRIBUTE, KILLED_INDEX_NAME,
+    SHED_KILLED_ATTRIBUTE, SHED_PRIME_WEIGHT_ATTRIBUTE, SORT_KEY_ATTRIBUTE, VERSION_ATTRIBUTE,
+};
+
+/// A [Sheep] killed by a [DynamoDBSheepShed] in tombstone mode, as it was
+/// when it was killed
+#[derive(Debug, Clone, Serialize)]
+pub struct KilledSheep {
+    #[serde(flatten)]
+    pub sheep: Sheep,
+    pub killed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub killed_by: Option<String>,
+}
+
+/// A page of [KilledSheep]s as returned by [DynamoDBSheepShed::list_killed]
+#[derive(Debug, Clone, Serialize)]
+pub struct KilledPage {
+    /// The [KilledSheep]s of the page, the most recently killed first
+    pub killed: Vec<KilledSheep>,
+    /// The [Cursor] to give back to retrieve the next page, if any
+    pub next_cursor: Option<Cursor>,
+}
+
+/// The position of [DynamoDBSheepShed::list_killed] in the partition of the shed
+/// in the [KILLED_INDEX_NAME], the payload of its [Cursor]s
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KilledPosition {
+    /// The [Tattoo] and the [KILLED_AT_ATTRIBUTE] of the last tombstone read,
+    /// [None] at the start of the partition
+    last: Option<(Tattoo, String)>,
+}
+
+/// The attributes a tombstone has on top of the ones of the [Sheep]
+#[derive(Deserialize)]
+struct Tombstone {
+    killed_at: DateTime<Utc>,
+    #[serde(default)]
+    killed_by: Option<String>,
+}
+
+impl KilledSheep {
+    fn from_tombstone(
+        item: HashMap<String, AttributeValue>,
+    ) -> Result<Self, sheep_shed::errors::Error> {
+        let Tombstone {
+            killed_at,
+            killed_by,
+        } = decode_item(item.clone())?;
+        let sheep = decode_item(item)?;
+        Ok(Self {
+            sheep,
+            killed_at,
+            killed_by,
+        })
+    }
+}
+
+impl DynamoDBSheepShed {
+    /// Turn the item of the [Sheep] into a tombstone, if it is still at its
+    /// `version`, and make it and its [sheep_shed::WeighIn]s expire after the
+    /// `retention`.
+    ///
+    /// The tombstone leaves the [crate::WEIGHT_INDEX_NAME] index as it loses its
+    /// [SHED_PRIME_WEIGHT_ATTRIBUTE] and enters the [KILLED_INDEX_NAME] one with its
+    /// [SHED_KILLED_ATTRIBUTE], and the counter of the shed is decremented in the
+    /// same transaction.
+    pub(crate) async fn _tombstone_sheep(
+        &self,
+        sheep: &Sheep,
+        retention: Duration,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        let tattoo = &sheep.tattoo;
+        let killed_at = Utc::now();
+        let expire_at = killed_at.timestamp().max(0) as u64 + retention.as_secs();
+        log::info!(
+            "_tombstone_sheep(tattoo={tattoo}, killed_at={killed_at}, expire_at={expire_at})"
+        );
+        let mut update_expression = format!(
+            "SET {KILLED_AT_ATTRIBUTE} = :killed_at, {SHED_KILLED_ATTRIBUTE} = :shed_killed, \
+            {EXPIRE_AT_ATTRIBUTE} = :expire_at, #version = :next_version"
+        );
+        if self.killer.is_some() {
+            update_expression.push_str(&format!(", {KILLED_BY_ATTRIBUTE} = :killed_by"));
+        }
+        update_expression.push_str(&format!(" REMOVE {SHED_PRIME_WEIGHT_ATTRIBUTE}"));
+        let update = Update::builder()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_key(tattoo)))
+            .update_expression(update_expression)
+            .condition_expression(version_condition(sheep.version))
+            .expression_attribute_names("#key", self.key_attribute.as_str())
+            .expression_attribute_names("#version", VERSION_ATTRIBUTE)
+            .expression_attribute_values(
+                ":killed_at",
+                AttributeValue::S(killed_at.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+            )
+            .expression_attribute_values(
+                ":shed_killed",
+                AttributeValue::S(self.shed_id.to_string()),
+            )
+            .expression_attribute_values(":expire_at", AttributeValue::N(expire_at.to_string()))
+            .expression_attribute_values(":version", AttributeValue::N(sheep.version.to_string()))
+            .expression_attribute_values(
+                ":next_version",
+                AttributeValue::N((sheep.version + 1).to_string()),
+            )
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld);
+        let update = match &self.killer {
+            Some(killer) => {
+                update.expression_attribute_values(":killed_by", AttributeValue::S(killer.clone()))
+            }
+            None => update,
+        }
+        .build()
+        .expect("table_name, key and update_expression are set");
+        self._write_with_counter(
+            TransactWriteItem::builder().update(update).build(),
+            -1,
+            |item| condition_failure(tattoo, item),
+        )
+        .await?;
+        self._set_weight_history_expiry(tattoo, Some(expire_at))
+            .await?;
+        log::info!("_tombstone_sheep => Ok({sheep})");
+        Ok(sheep.clone())
+    }
+
+    /// Set (or remove with [None]) the [EXPIRE_AT_ATTRIBUTE] of all the
+    /// [sheep_shed::WeighIn]s of the [Sheep] with the given [Tattoo]
+    async fn _set_weight_history_expiry(
+        &self,
+        tattoo: &Tattoo,
+        expire_at: Option<u64>,
+    ) -> Result<(), sheep_shed::errors::Error> {
+        log::info!("_set_weight_history_expiry(tattoo={tattoo}, expire_at={expire_at:?})");
+        let requests = self
+            ._weigh_in_items(tattoo)
+            .await?
+            .into_iter()
+            .map(|mut item| {
+                match expire_at {
+                    Some(expire_at) => item.insert(
+                        EXPIRE_AT_ATTRIBUTE.to_owned(),
+                        AttributeValue::N(expire_at.to_string()),
+                    ),
+                    None => item.remove(EXPIRE_AT_ATTRIBUTE),
+                };
+                let put = PutRequest::builder()
+                    .set_item(Some(item))
+                    .build()
+                    .expect("item is set");
+                WriteRequest::builder().put_request(put).build()
+            })
+            .collect();
+        self._batch_write(requests).await
+    }
+
+    /// List a page of at most `limit` [KilledSheep]s of the shed whose tombstone is
+    /// not purged yet, the most recently killed first, starting after the `cursor`
+    /// returned with the previous page (from the start with [None]).
+    ///
+    /// The tombstones are found by `Query`s of the partition of the shed in the
+    /// [KILLED_INDEX_NAME], in descending [KILLED_AT_ATTRIBUTE] order: only the
+    /// tombstones of the shed are read, whatever the size of the table. A tombstone
+    /// that does not decode is logged and skipped.
+    ///
+    /// Like the ones of [sheep_shed::AsyncSheepShed::list_page], the [Cursor] is
+    /// signed with the cursor key of the [DynamoDBSheepShed] and the
+    /// [sheep_shed::ShedId], so another shed refuses it, and the index is eventually
+    /// consistent, so a [Sheep] just killed may be missing.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::InvalidConfiguration] if the
+    /// [DynamoDBSheepShed] has no cursor key (see [DynamoDBSheepShed::with_cursor_key]),
+    /// or an [sheep_shed::errors::Error::IndexNotAvailable] if the table does not
+    /// have the [KILLED_INDEX_NAME] index.
+    pub async fn list_killed(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<KilledPage, sheep_shed::errors::Error> {
+        log::info!("list_killed(cursor={cursor:?}, limit={limit})");
+        let cursor_key = self.signing_cursor_key()?;
+        // Not the scope of the cursors of list_page, so they cannot be mixed up
+        let scope = format!("{}#{KILLED_INDEX_NAME}", self.shed_id);
+        let mut position = cursor
+            .map(|c| {
+                let payload = c.open_scoped(scope.as_bytes(), cursor_key)?;
+                serde_json::from_slice::<KilledPosition>(&payload)
+                    .map_err(|_| sheep_shed::errors::Error::InvalidCursor(c.to_string()))
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let shed_killed = AttributeValue::S(self.shed_id.to_string());
+        let mut killed = vec![];
+        let next_position = loop {
+            if killed.len() == limit {
+                break Some(position);
+            }
+            let exclusive_start_key = position.last.as_ref().map(|(tattoo, killed_at)| {
+                HashMap::from([
+                    (
+                        self.key_attribute.clone(),
+                        to_attribute_value(tattoo).expect("cannot fail"),
+                    ),
+                    (SORT_KEY_ATTRIBUTE.to_owned(), sheep_sort_key(&self.shed_id)),
+                    (SHED_KILLED_ATTRIBUTE.to_owned(), shed_killed.clone()),
+                    (
+                        KILLED_AT_ATTRIBUTE.to_owned(),
+                        AttributeValue::S(killed_at.clone()),
+                    ),
+                ])
+            });
+            let query = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .index_name(KILLED_INDEX_NAME)
+                .key_condition_expression("#shed_killed = :shed_killed")
+                .expression_attribute_names("#shed_killed", SHED_KILLED_ATTRIBUTE)
+                .expression_attribute_values(":shed_killed", shed_killed.clone())
+                .scan_index_forward(false)
+                .set_exclusive_start_key(exclusive_start_key)
+                .limit((limit - killed.len()).try_into().unwrap_or(i32::MAX));
+            let result = self
+                .retry_config
+                .call("query", || query.clone().send())
+                .await
+                .map_err(|e| index_query_error(KILLED_INDEX_NAME, e))?;
+            for item in result.items.unwrap_or_default() {
+                let tattoo = item.get(self.key_attribute.as_str()).cloned();
+                match KilledSheep::from_tombstone(item) {
+                    Ok(killed_sheep) => killed.push(killed_sheep),
+                    Err(e) => log::error!("skipping the tombstone of tattoo {tattoo:?}: {e}"),
+                }
+            }
+            position = match result.last_evaluated_key {
+                // The last evaluated key is always a tombstone of the partition
+                Some(key) => KilledPosition {
+                    last: Some(
+                        key.get(self.key_attribute.as_str())
+                            .zip(key.get(KILLED_AT_ATTRIBUTE))
+                            .and_then(|(tattoo, killed_at)| {
+                                Some((
+                                    from_attribute_value(tattoo.clone()).ok()?,
+                                    killed_at.as_s().ok()?.clone(),
+                                ))
+                            })
+                            .ok_or_else(|| {
+                                let err_string = format!("unexpected last evaluated key {key:?}");
+                                log::error!("{err_string}");
+                                sheep_shed::errors::Error::GenericError(err_string)
+                            })?,
+                    ),
+                },
+                None => break None,
+            };
+        };
+        let next_cursor = next_position.map(|position| {
+            Cursor::seal_scoped(
+                &serde_json::to_vec(&position).expect("cannot fail"),
+                scope.as_bytes(),
+                cursor_key,
+            )
+        });
+        log::info!(
+            "list_killed => Ok(killed.len()={}, next_cursor={next_cursor:?})",
+            killed.len()
+        );
+        Ok(KilledPage {
+            killed,
+            next_cursor,
+        })
+    }
+
+    /// Bring a killed [Sheep] back to life from its tombstone, with its weight
+    /// history, and return it.
+    /// # Errors
+    /// Will return an [sheep_shed::errors::Error::SheepNotPresent] if there is no
+    /// tombstone for the [Tattoo] (never killed in tombstone mode, or already
+    /// purged), an [sheep_shed::errors::Error::SheepDuplicationError] if the
+    /// [Sheep] is alive and an [sheep_shed::errors::Error::ConcurrentModification]
+    /// if the tombstone changed during the resurrection.
+    pub async fn resurrect_sheep(
+        &mut self,
+        tattoo: &Tattoo,
+    ) -> Result<Sheep, sheep_shed::errors::Error> {
+        log::info!("resurrect_sheep(tattoo={tattoo})");
+        let get_item = self
+            .client
+            .get_item()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_key(tattoo)))
+            .consistent_read(true);
+        let tombstone = self
+            .retry_config
+            .call("get_item", || get_item.clone().send())
+            .await
+            .map_err(database_error)?
+            .item
+            .ok_or_else(|| sheep_shed::errors::Error::SheepNotPresent(tattoo.clone()))?;
+        if !is_tombstone(&tombstone) {
+            return Err(sheep_shed::errors::Error::SheepDuplicationError(
+                tattoo.clone(),
+            ));
+        }
+        let sheep: Sheep = decode_item(tombstone)?;
+        let update = Update::builder()
+            .table_name(self.table_name.as_str())
+            .set_key(Some(self.sheep_key(tattoo)))
+            .update_expression(format!(
+                "SET {SHED_PRIME_WEIGHT_ATTRIBUTE} = :shed_prime_weight, #version = :next_version \
+                REMOVE {KILLED_AT_ATTRIBUTE}, {KILLED_BY_ATTRIBUTE}, {SHED_KILLED_ATTRIBUTE}, \
+                {EXPIRE_AT_ATTRIBUTE}"
+            ))
+            .condition_expression(format!(
+                "attribute_exists({KILLED_AT_ATTRIBUTE}) AND #version = :version"
+            ))
+            .expression_attribute_names("#version", VERSION_ATTRIBUTE)
+            .expression_attribute_values(
+                ":shed_prime_weight",
+                shed_prime_weight_value(&self.shed_id, &sheep.weight),
+            )
+            .expression_attribute_values(":version", AttributeValue::N(sheep.version.to_string()))
+            .expression_attribute_values(
+                ":next_version",
+                AttributeValue::N((sheep.version + 1).to_string()),
+            )
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .build()
+            .expect("table_name, key and update_expression are set");
+        self._write_with_counter(
+            TransactWriteItem::builder().update(update).build(),
+            1,
+            |item| {
+                if item.is_some() {
+                    sheep_shed::errors::Error::ConcurrentModification(tattoo.clone())
+                } else {
+                    sheep_shed::errors::Error::SheepNotPresent(tattoo.clone())
+                }
+            },
+        )
+        .await?;
+        self._set_weight_history_expiry(tattoo, None).await?;
+        let sheep = Sheep {
+            version: sheep.version + 1,
+            ..sheep
+        };
+        log::info!("resurrect_sheep => Ok({sheep})");
+        Ok(sheep)
+    }
+}
@@ -17,6 +17,8 @@ pub enum SimpleError {
     Unauthorized,
     #[error("Server error: {0}")]
     ServerError(&'static str),
+    #[error("Service unavailable, retry after {retry_after} seconds")]
+    Unavailable { retry_after: u64 },
     #[error("Custom error: {code} {message}")]
     Custom { code: u16, message: String },
 }
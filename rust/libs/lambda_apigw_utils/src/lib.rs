@@ -4,14 +4,14 @@ pub use lambda_commons_utils::aws_lambda_events::apigw::{
 pub use lambda_http;
 use lambda_http::{
     http::{
-        header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE},
+        header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE, RETRY_AFTER},
         HeaderMap,
     },
     Body, Error,
 };
 use serde_json::{json, Value};
 
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
 pub mod errors;
 pub use errors::SimpleError;
@@ -31,12 +31,22 @@ pub struct CognitoValues<'a> {
     pub email: Option<&'a str>,
     /// The Cognito username, if the `cognito:username` field is present
     pub username: Option<&'a str>,
+    /// The tenant of the user, if the `custom:tenant` field is present
+    pub tenant: Option<&'a str>,
+}
+
+impl CognitoValues<'_> {
+    /// The tenant the user belongs to: the `custom:tenant` field if present,
+    /// else the user is its own tenant and this is its [CognitoValues::user_id]
+    pub fn tenant_id(&self) -> &str {
+        self.tenant.unwrap_or(self.user_id)
+    }
 }
 
 /// Given an immutable reference to a [Request], returns a [CognitoValues] structure
 /// if the [Request] was received from an API Gatway with Lambda proxy integration.
 /// Returns [None] if Cognito claims are not present.
-pub fn extract_cognito_values(event: &ApiGatewayProxyRequest) -> Option<CognitoValues> {
+pub fn extract_cognito_values(event: &ApiGatewayProxyRequest) -> Option<CognitoValues<'_>> {
     if let Some(Value::Object(claims)) = event.request_context.authorizer.fields.get("claims") {
         return Some(CognitoValues {
             user_id: claims
@@ -52,11 +62,29 @@ pub fn extract_cognito_values(event: &ApiGatewayProxyRequest) -> Option<CognitoV
                 .get("cognito:username")
                 .filter(|&v| v.is_string())
                 .map(|v| v.as_str().expect("just tested it's a string")),
+            tenant: claims
+                .get("custom:tenant")
+                .filter(|&v| v.is_string())
+                .map(|v| v.as_str().expect("just tested it's a string")),
         });
     }
     None
 }
 
+/// Given the [CognitoValues] of a request, returns the tenant of the user (see
+/// [CognitoValues::tenant_id]) parsed as a `T`.
+/// # Errors
+/// Returns [SimpleError::Unauthorized] if the request was not authenticated by
+/// Cognito, or the error of the parsing of the tenant.
+pub fn extract_tenant_id<T>(cognito_values: Option<&CognitoValues>) -> Result<T, SimpleError>
+where
+    T: FromStr,
+    SimpleError: From<T::Err>,
+{
+    let tenant_id = cognito_values.ok_or(SimpleError::Unauthorized)?.tenant_id();
+    Ok(tenant_id.parse()?)
+}
+
 /// Given an immutable reference to a [Request], returns a [HashMap] structure
 /// of [HashMap<&str, &str>]
 /// Returns [None] if Cognito claims are not present.
@@ -73,16 +101,23 @@ pub fn extract_parameters(event: &ApiGatewayProxyRequest) -> HashMap<&str, &str>
 }
 
 pub fn extract_body(event: &ApiGatewayProxyRequest) -> &str {
-    event.body.as_ref().map(|s| s.as_str()).unwrap_or("")
+    event.body.as_deref().unwrap_or("")
 }
 
 pub fn standard_response(
     simple_response: SimpleResponse,
 ) -> Result<ApiGatewayProxyResponse, Error> {
-    let SimpleResponse { code, body } = simple_response;
+    let SimpleResponse {
+        code,
+        body,
+        retry_after,
+    } = simple_response;
     let status_code = code as i64;
     let mut headers = HeaderMap::new();
     headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    if let Some(retry_after) = retry_after {
+        headers.insert(RETRY_AFTER, retry_after.into());
+    }
     headers.insert(
         ACCESS_CONTROL_ALLOW_ORIGIN,
         std::env::var("ALLOW_ORIGIN")
@@ -118,6 +153,8 @@ pub struct SimpleRequest<'a> {
 pub struct SimpleResponse {
     pub code: u16,
     pub body: Option<Value>,
+    // The number of seconds to put in the `Retry-After` header, if any
+    pub retry_after: Option<u64>,
 }
 #[macro_export]
 macro_rules! simple_response {
@@ -125,12 +162,14 @@ macro_rules! simple_response {
         Ok($crate::SimpleResponse {
             code: $code,
             body: None,
+            retry_after: None,
         })
     };
     ($code:literal, $($body:tt)+) => {
         Ok($crate::SimpleResponse {
             code: $code,
             body: Some($($body)+),
+            retry_after: None,
         })
     };
 }
@@ -142,16 +181,25 @@ impl From<SimpleError> for SimpleResponse {
             SimpleError::NotFound { .. } => 404,
             SimpleError::Unauthorized => 401,
             SimpleError::ServerError(_) | SimpleError::InvalidState(_) => 500,
+            SimpleError::Unavailable { retry_after } => {
+                return SimpleResponse {
+                    code: 503,
+                    body: Some(json!({"message": value.to_string()})),
+                    retry_after: Some(retry_after),
+                }
+            }
             SimpleError::Custom { code, message } => {
                 return SimpleResponse {
                     code,
                     body: Some(json!({"message": message})),
+                    retry_after: None,
                 }
             }
         };
         SimpleResponse {
             code,
             body: Some(json!({"message": value.to_string()})),
+            retry_after: None,
         }
     }
 }
@@ -305,6 +353,7 @@ pub mod prelude {
     pub use super::lambda_commons_utils::log;
     pub use super::serde_json::{self, json};
     pub use super::{
-        auth_lambda_main, lambda_main, simple_response, SimpleError, SimpleRequest, SimpleResult,
+        auth_lambda_main, extract_tenant_id, lambda_main, simple_response, SimpleError,
+        SimpleRequest, SimpleResult,
     };
 }
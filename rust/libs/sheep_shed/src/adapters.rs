@@ -0,0 +1,164 @@
+use futures::{stream, Stream};
+
+use crate::{
+    errors::Error, AddSheepOutcome, AsyncSheepShed, Cursor, Sheep, SheepPage, SheepShed, Tattoo,
+    WeighIn, Weight, WeightCriteria,
+};
+
+/// Expose an [AsyncSheepShed] as a [SheepShed].
+/// # Important note
+/// Each call is bridged with [tokio::runtime::Handle::block_on], so it MUST
+/// be made from a blocking thread of a [tokio::runtime::Runtime]
+/// (e.g. inside [tokio::task::spawn_blocking]).
+#[derive(Debug, Clone, Default)]
+pub struct SyncAdapter<S>(pub S);
+
+impl<S: AsyncSheepShed + Send + Sync> SheepShed for SyncAdapter<S> {
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn add_sheep(&mut self, sheep: Sheep) -> Result<(), Error> {
+        tokio::runtime::Handle::current().block_on(self.0.add_sheep(sheep))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> Result<Vec<(Tattoo, AddSheepOutcome)>, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.add_sheep_batch(sheeps))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn sheep_count(&self) -> Result<usize, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.sheep_count())
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.get_sheep(tattoo))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn sheep_iter(&self) -> Result<impl Iterator<Item = Sheep>, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.sheep_iter())
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn list_page(&self, cursor: Option<&Cursor>, limit: usize) -> Result<SheepPage, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.list_page(cursor, limit))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn heaviest_matching(&self, criteria: WeightCriteria) -> Result<Option<Sheep>, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.heaviest_matching(criteria))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.kill_sheep(tattoo))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn kill_sheep_if_unchanged(&mut self, sheep: &Sheep) -> Result<Sheep, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.kill_sheep_if_unchanged(sheep))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn update_weight(&mut self, tattoo: &Tattoo, weight: Weight) -> Result<Sheep, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.update_weight(tattoo, weight))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn weight_history(&self, tattoo: &Tattoo) -> Result<Vec<WeighIn>, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.weight_history(tattoo))
+    }
+
+    /// # Panics
+    /// Panics if called outside of a blocking thread ([tokio::task::spawn_blocking]).
+    fn transfer_sheep(&mut self, to: &mut Self, tattoo: &Tattoo) -> Result<Sheep, Error> {
+        tokio::runtime::Handle::current().block_on(self.0.transfer_sheep(&mut to.0, tattoo))
+    }
+}
+
+/// Expose a [SheepShed] as an [AsyncSheepShed].
+/// # Important note
+/// The sync methods are called directly from the async ones, so this adapter
+/// should only wrap a [SheepShed] that never blocks for long (e.g. a
+/// [crate::MemorySheepShed]), otherwise it will stall the async executor.
+#[derive(Debug, Clone, Default)]
+pub struct AsyncAdapter<S>(pub S);
+
+impl<S: SheepShed + Send + Sync> AsyncSheepShed for AsyncAdapter<S> {
+    async fn add_sheep(&mut self, sheep: Sheep) -> Result<(), Error> {
+        self.0.add_sheep(sheep)
+    }
+
+    async fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> Result<Vec<(Tattoo, AddSheepOutcome)>, Error> {
+        self.0.add_sheep_batch(sheeps)
+    }
+
+    async fn sheep_count(&self) -> Result<usize, Error> {
+        self.0.sheep_count()
+    }
+
+    async fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, Error> {
+        self.0.get_sheep(tattoo)
+    }
+
+    /// The [Iterator] returned by the inner [SheepShed] is not necessarily
+    /// [Send] so it is collected before being returned.
+    async fn sheep_iter(&self) -> Result<impl Iterator<Item = Sheep> + Send, Error> {
+        Ok(self.0.sheep_iter()?.collect::<Vec<_>>().into_iter())
+    }
+
+    /// Same as [AsyncAdapter::sheep_iter], the [Sheep]s are collected before
+    /// being streamed.
+    fn sheep_stream(&self) -> impl Stream<Item = Result<Sheep, Error>> + Send + '_ {
+        let sheeps = match self.0.sheep_iter() {
+            Ok(iter) => iter.map(Ok).collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(sheeps)
+    }
+
+    async fn list_page(&self, cursor: Option<&Cursor>, limit: usize) -> Result<SheepPage, Error> {
+        self.0.list_page(cursor, limit)
+    }
+
+    async fn heaviest_matching(&self, criteria: WeightCriteria) -> Result<Option<Sheep>, Error> {
+        self.0.heaviest_matching(criteria)
+    }
+
+    async fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, Error> {
+        self.0.kill_sheep(tattoo)
+    }
+
+    async fn kill_sheep_if_unchanged(&mut self, sheep: &Sheep) -> Result<Sheep, Error> {
+        self.0.kill_sheep_if_unchanged(sheep)
+    }
+
+    async fn update_weight(&mut self, tattoo: &Tattoo, weight: Weight) -> Result<Sheep, Error> {
+        self.0.update_weight(tattoo, weight)
+    }
+
+    async fn weight_history(&self, tattoo: &Tattoo) -> Result<Vec<WeighIn>, Error> {
+        self.0.weight_history(tattoo)
+    }
+
+    async fn transfer_sheep(&mut self, to: &mut Self, tattoo: &Tattoo) -> Result<Sheep, Error> {
+        self.0.transfer_sheep(&mut to.0, tattoo)
+    }
+}
@@ -0,0 +1,145 @@
+use std::{fmt::Display, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{errors::Error, Sheep};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// An opaque continuation token returned by [crate::SheepShed::list_page].
+///
+/// It is made of a payload chosen by the shed implementation (e.g. the last key
+/// it returned) and of an HMAC-SHA256 signature of this payload, both encoded in
+/// URL-safe base64 so it can be used as-is in a query string. A [Cursor] that has
+/// been tampered with is rejected with an [Error::InvalidCursor].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cursor(String);
+
+impl Cursor {
+    /// Create a [Cursor] containing the given `payload`, signed with `key`
+    pub fn seal(payload: &[u8], key: &[u8]) -> Self {
+        Self::seal_scoped(payload, b"", key)
+    }
+
+    /// Like [Cursor::seal], but the signature also covers the `scope` (e.g. the
+    /// shed issuing the [Cursor]) without it being part of the [Cursor], so it
+    /// can only be opened in the same `scope`, see [Cursor::open_scoped]
+    pub fn seal_scoped(payload: &[u8], scope: &[u8], key: &[u8]) -> Self {
+        let signature = Self::mac(payload, scope, key).finalize().into_bytes();
+        Self(format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// Verify the signature of the [Cursor] with `key` and return its payload
+    /// # Errors
+    /// Returns an [Error::InvalidCursor] if the [Cursor] is malformed or if its
+    /// signature does not match its payload
+    pub fn open(&self, key: &[u8]) -> Result<Vec<u8>, Error> {
+        self.open_scoped(b"", key)
+    }
+
+    /// Verify the signature of a [Cursor] created by [Cursor::seal_scoped] with
+    /// `key` in the same `scope` and return its payload
+    /// # Errors
+    /// Returns an [Error::InvalidCursor] if the [Cursor] is malformed or if its
+    /// signature does not match its payload and `scope`
+    pub fn open_scoped(&self, scope: &[u8], key: &[u8]) -> Result<Vec<u8>, Error> {
+        let invalid = || Error::InvalidCursor(self.0.clone());
+        let (payload, signature) = self.0.split_once('.').ok_or_else(invalid)?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+        Self::mac(&payload, scope, key)
+            .verify_slice(&signature)
+            .map_err(|_| invalid())?;
+        Ok(payload)
+    }
+
+    /// The HMAC of the `scope`, prefixed by its length so it cannot bleed into
+    /// the `payload`, followed by the `payload`
+    fn mac(payload: &[u8], scope: &[u8], key: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
+        mac.update(&(scope.len() as u64).to_be_bytes());
+        mac.update(scope);
+        mac.update(payload);
+        mac
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A [Cursor] is opaque, parsing never fails: the validity of the [Cursor] is
+/// only checked by the shed that issued it.
+impl FromStr for Cursor {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_owned()))
+    }
+}
+
+/// A page of [Sheep]s as returned by [crate::SheepShed::list_page]
+#[derive(Debug, Clone, Serialize)]
+pub struct SheepPage {
+    /// The [Sheep]s of the page
+    pub sheeps: Vec<Sheep>,
+    /// The [Cursor] to give back to retrieve the next page, if any
+    pub next_cursor: Option<Cursor>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"secret";
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = Cursor::seal(br#"{"tattoo":42}"#, KEY);
+        let parsed: Cursor = cursor.to_string().parse().unwrap();
+        assert_eq!(parsed.open(KEY).unwrap(), br#"{"tattoo":42}"#);
+    }
+
+    #[test]
+    fn cursor_tampering_is_detected() {
+        let cursor = Cursor::seal(br#"{"tattoo":42}"#, KEY);
+        let (_, signature) = cursor.0.split_once('.').unwrap();
+        let forged = Cursor(format!(
+            "{}.{signature}",
+            URL_SAFE_NO_PAD.encode(br#"{"tattoo":43}"#)
+        ));
+        assert!(forged
+            .open(KEY)
+            .is_err_and(|e| matches!(e, Error::InvalidCursor(_))));
+        // Wrong key
+        assert!(cursor.open(b"other").is_err());
+        // Garbage
+        assert!("not-a-cursor".parse::<Cursor>().unwrap().open(KEY).is_err());
+    }
+
+    #[test]
+    fn cursor_scope_is_enforced() {
+        let cursor = Cursor::seal_scoped(br#"{"tattoo":42}"#, b"farm", KEY);
+        assert_eq!(
+            cursor.open_scoped(b"farm", KEY).unwrap(),
+            br#"{"tattoo":42}"#
+        );
+        assert!(cursor
+            .open_scoped(b"other-farm", KEY)
+            .is_err_and(|e| matches!(e, Error::InvalidCursor(_))));
+        assert!(cursor.open(KEY).is_err());
+        // The scope cannot be moved into the payload
+        let cursor = Cursor::seal_scoped(b"mpayload", b"far", KEY);
+        assert!(cursor.open_scoped(b"farm", KEY).is_err());
+    }
+}
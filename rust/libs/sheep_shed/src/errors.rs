@@ -1,7 +1,11 @@
 use lambda_apigw_utils::SimpleError;
 use thiserror::Error;
 
-use crate::Tattoo;
+use crate::{ShedId, Tattoo, TenantId, Weight};
+
+/// The number of seconds a client is asked to wait before retrying a request
+/// that failed with [Error::Throttled] or [Error::Transient]
+pub const RETRY_AFTER_SECS: u64 = 1;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -9,6 +13,48 @@ pub enum Error {
     SheepNotPresent(Tattoo),
     #[error("Sheep already in the shed: {0}")]
     SheepDuplicationError(Tattoo),
+    #[error("Sheep was modified concurrently: {0}")]
+    ConcurrentModification(Tattoo),
+    #[error("Unknown weight unit: {0}")]
+    UnknownWeightUnit(String),
+    #[error("Cannot parse weight {0:?}: {1}")]
+    WeightParseError(String, String),
+    #[error("Invalid precision: {0}")]
+    InvalidPrecision(String),
+    #[error(
+        "Invalid weight {0}: a sheep weights between {} and {}",
+        Weight::MIN,
+        Weight::MAX
+    )]
+    InvalidWeight(Weight),
+    #[error("Weight overflow")]
+    WeightOverflow,
+    #[error(
+        "Invalid shed id {0:?}: expected 1 to {} ASCII letters, digits, '-' or '_'",
+        ShedId::MAX_LEN
+    )]
+    InvalidShedId(String),
+    #[error(
+        "Invalid tenant id {0:?}: expected 1 to {} ASCII letters, digits, '-' or '_'",
+        TenantId::MAX_LEN
+    )]
+    InvalidTenantId(String),
+    #[error("Tenant {0} cannot reach the sheds of another tenant")]
+    ForeignTenant(TenantId),
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("Index not available: {0}")]
+    IndexNotAvailable(String),
+    #[error("Throttled by the database: {0}")]
+    Throttled(String),
+    #[error("Transient database error: {0}")]
+    Transient(String),
+    #[error("Table not found: {0}")]
+    TableNotFound(String),
+    #[error("Access denied to the database: {0}")]
+    AccessDenied(String),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
     #[error("Generic error: {0}")]
     GenericError(String),
 }
@@ -17,12 +63,36 @@ impl From<Error> for SimpleError {
     fn from(value: Error) -> Self {
         lambda_apigw_utils::lambda_commons_utils::log::error!("sheep_shed::Error: {value}");
         match value {
-            Error::SheepDuplicationError(_) => SimpleError::InvalidInput(value.to_string()),
+            Error::SheepDuplicationError(_)
+            | Error::UnknownWeightUnit(_)
+            | Error::WeightParseError(_, _)
+            | Error::InvalidPrecision(_)
+            | Error::InvalidWeight(_)
+            | Error::InvalidShedId(_)
+            | Error::InvalidCursor(_) => SimpleError::InvalidInput(value.to_string()),
+            // The tenant comes from the token of the user, not from its input
+            Error::InvalidTenantId(_) => SimpleError::Unauthorized,
+            Error::ForeignTenant(_) => SimpleError::Custom {
+                code: 403,
+                message: value.to_string(),
+            },
             Error::SheepNotPresent(_) => SimpleError::Custom {
                 code: 404,
                 message: value.to_string(),
             },
-            Error::GenericError(_) => Self::ServerError("Please try again later"),
+            Error::ConcurrentModification(_) => SimpleError::Custom {
+                code: 409,
+                message: value.to_string(),
+            },
+            Error::Throttled(_) | Error::Transient(_) => SimpleError::Unavailable {
+                retry_after: RETRY_AFTER_SECS,
+            },
+            Error::WeightOverflow
+            | Error::IndexNotAvailable(_)
+            | Error::TableNotFound(_)
+            | Error::AccessDenied(_)
+            | Error::InvalidConfiguration(_)
+            | Error::GenericError(_) => Self::ServerError("Please try again later"),
         }
     }
 }
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Weight, WeightFormat};
+
+/// A weigh-in of a [crate::Sheep]: the [Weight] it had at a given time.
+///
+/// A [crate::SheepShed] records one when a [crate::Sheep] is added and then
+/// one for each weight update, see [crate::SheepShed::weight_history].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeighIn {
+    pub weighed_at: DateTime<Utc>,
+    pub weight: Weight,
+}
+
+impl WeighIn {
+    /// Create a [WeighIn] of the given [Weight], timestamped now
+    pub fn now(weight: Weight) -> Self {
+        Self {
+            weighed_at: Utc::now(),
+            weight,
+        }
+    }
+
+    /// Return a serializable version of the [WeighIn] whose [Weight] is printed
+    /// following the [WeightFormat]. With [None], it is serialized exactly like
+    /// the [WeighIn] itself.
+    pub fn formatted(&self, format: Option<WeightFormat>) -> FormattedWeighIn {
+        FormattedWeighIn {
+            weigh_in: *self,
+            format,
+        }
+    }
+}
+
+/// A [WeighIn] serialized with a [WeightFormat], see [WeighIn::formatted]
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedWeighIn {
+    weigh_in: WeighIn,
+    format: Option<WeightFormat>,
+}
+impl Serialize for FormattedWeighIn {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let WeighIn { weighed_at, weight } = self.weigh_in;
+        let mut weigh_in = serializer.serialize_struct("WeighIn", 2)?;
+        weigh_in.serialize_field("weighed_at", &weighed_at)?;
+        match self.format {
+            Some(format) => {
+                weigh_in.serialize_field("weight", &weight.display_with(format).to_string())?
+            }
+            None => weigh_in.serialize_field("weight", &weight)?,
+        }
+        weigh_in.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WeightUnit;
+
+    #[test]
+    fn weigh_in_serialization() {
+        let weigh_in = WeighIn {
+            weighed_at: "2024-03-01T08:30:00Z".parse().unwrap(),
+            weight: Weight::from_unit(80.5, WeightUnit::Kilograms),
+        };
+        let json = serde_json::to_value(weigh_in).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"weighed_at": "2024-03-01T08:30:00Z", "weight": 80_500_000_000u64})
+        );
+        assert_eq!(serde_json::from_value::<WeighIn>(json).unwrap(), weigh_in);
+        assert_eq!(
+            serde_json::to_value(weigh_in.formatted(None)).unwrap(),
+            serde_json::to_value(weigh_in).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(
+                weigh_in.formatted(Some(WeightFormat::default().unit(WeightUnit::Grams)))
+            )
+            .unwrap(),
+            serde_json::json!({"weighed_at": "2024-03-01T08:30:00Z", "weight": "80500.000g"})
+        );
+    }
+}
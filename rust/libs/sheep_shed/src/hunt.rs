@@ -0,0 +1,74 @@
+//! The hunt of the wolves, shared by every [AsyncSheepShed], see [hunt]
+
+use futures::{future, TryStreamExt};
+use lambda_apigw_utils::lambda_commons_utils::log;
+
+use crate::{errors::Error, AsyncSheepShed, Sheep, WeightCriteria};
+
+/// Return the heaviest [Sheep] whose [crate::Weight] matches the [WeightCriteria]
+/// by going through all the [Sheep]s with [AsyncSheepShed::sheep_stream], each one
+/// being examined as soon as it is retrieved.
+pub async fn heaviest_in_stream<S: AsyncSheepShed + Sync + ?Sized>(
+    sheep_shed: &S,
+    criteria: WeightCriteria,
+) -> Result<Option<Sheep>, Error> {
+    sheep_shed
+        .sheep_stream()
+        .try_filter(|sheep| future::ready(criteria.matches(&sheep.weight)))
+        .try_fold(None, |heaviest: Option<Sheep>, sheep| {
+            future::ready(Ok(match heaviest {
+                Some(heaviest) if heaviest.weight > sheep.weight => Some(heaviest),
+                _ => Some(sheep),
+            }))
+        })
+        .await
+}
+
+/// Kill the heaviest [Sheep] whose [crate::Weight] matches the [WeightCriteria]
+/// and return it, or [None] if no [Sheep] matches.
+///
+/// The shed is asked first with [AsyncSheepShed::heaviest_matching] and, if it
+/// cannot answer (see [Error::IndexNotAvailable]), it is streamed with
+/// [heaviest_in_stream].
+///
+/// Other hunters may be hunting at the same time: the [Sheep] is only killed if it
+/// did not change since it was picked (see [AsyncSheepShed::kill_sheep_if_unchanged]),
+/// otherwise another one is picked, up to `max_attempts` times.
+/// # Errors
+/// Will return an [Error::ConcurrentModification] if the [Sheep] picked by the
+/// last attempt was modified or killed in the meantime.
+pub async fn hunt<S: AsyncSheepShed + Sync>(
+    sheep_shed: &mut S,
+    criteria: WeightCriteria,
+    max_attempts: u32,
+) -> Result<Option<Sheep>, Error> {
+    for attempt in 1..=max_attempts {
+        log::info!(
+            "looking for the heaviest {criteria:?} sheep (attempt {attempt}/{max_attempts})..."
+        );
+        let heaviest = match sheep_shed.heaviest_matching(criteria).await {
+            Err(Error::IndexNotAvailable(e)) => {
+                log::warn!("falling back to a full scan: {e}");
+                heaviest_in_stream(sheep_shed, criteria).await
+            }
+            result => result,
+        };
+        let Some(sheep) = heaviest? else {
+            return Ok(None);
+        };
+
+        log::info!("killing {sheep}");
+        match sheep_shed.kill_sheep_if_unchanged(&sheep).await {
+            Ok(sheep) => return Ok(Some(sheep)),
+            // Another hunter was faster, or the sheep was weighed in the meantime
+            Err(e @ (Error::ConcurrentModification(_) | Error::SheepNotPresent(_))) => {
+                log::warn!("lost the race for {sheep}: {e}");
+                if attempt == max_attempts {
+                    return Err(Error::ConcurrentModification(sheep.tattoo));
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
@@ -1,8 +1,66 @@
+mod adapters;
+mod cursor;
 pub mod errors;
+mod history;
+mod hunt;
+pub mod primality;
 mod sheep;
 
-pub use sheep::{Sheep, Tattoo, Weight, WeightUnit};
-use std::collections::HashMap;
+pub use adapters::{AsyncAdapter, SyncAdapter};
+pub use cursor::{Cursor, SheepPage};
+use futures::{stream, Stream};
+pub use history::{FormattedWeighIn, WeighIn};
+pub use hunt::{heaviest_in_stream, hunt};
+use serde::Serialize;
+pub use sheep::{
+    weight_human, FormattedSheep, Sex, ShedId, Sheep, Tattoo, TenantId, Weight, WeightDisplay,
+    WeightFormat, WeightUnit,
+};
+use std::{collections::HashMap, future::Future};
+
+/// The outcome of the insertion of one [Sheep] of a batch,
+/// see [SheepShed::add_sheep_batch]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AddSheepOutcome {
+    /// The [Sheep] is now in the shed
+    Inserted,
+    /// A [Sheep] with the same [Tattoo] was already in the shed (or earlier in the batch)
+    Duplicate,
+    /// The [Sheep] could not be inserted for another reason
+    Failed { reason: String },
+}
+
+/// A criteria on the [Weight] of a [Sheep], see [SheepShed::heaviest_matching]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightCriteria {
+    /// Any [Weight] matches
+    Any,
+    /// Only a [Weight] that is a prime number when expressed in micrograms matches
+    PrimeUg,
+}
+
+impl WeightCriteria {
+    /// Return `true` if the [Weight] matches the [WeightCriteria]
+    pub fn matches(&self, weight: &Weight) -> bool {
+        match self {
+            WeightCriteria::Any => true,
+            WeightCriteria::PrimeUg => weight.is_prime_ug(),
+        }
+    }
+}
+
+impl From<Result<(), errors::Error>> for AddSheepOutcome {
+    fn from(value: Result<(), errors::Error>) -> Self {
+        match value {
+            Ok(()) => AddSheepOutcome::Inserted,
+            Err(errors::Error::SheepDuplicationError(_)) => AddSheepOutcome::Duplicate,
+            Err(e) => AddSheepOutcome::Failed {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
 
 /// The trait for [SheepShed] that can hold [Sheep]s and provides basic methods
 /// for interacting with it.
@@ -12,29 +70,265 @@ pub trait SheepShed {
     /// It is not allowed to add a duplicated [Sheep], will return an
     /// [errors::Error::SheepDuplicationError] if the user tries to add
     /// a [Sheep] with an already known [Tattoo]
+    ///
+    /// Will return an [errors::Error::InvalidWeight] if the [Sheep] is not valid
+    /// (see [Sheep::validate])
+    ///
+    /// The [Weight] of the [Sheep] is recorded as the first [WeighIn] of its history
+    /// and its `version` is reset to 0.
     fn add_sheep(&mut self, sheep: Sheep) -> Result<(), errors::Error>;
+    /// Add a batch of new [Sheep]s in the [SheepShed]
+    ///
+    /// Return the [AddSheepOutcome] of each [Sheep], in the same order as the batch.
+    /// A [Sheep] failing to be inserted does not prevent the others to be.
+    ///
+    /// The default implementation simply calls [SheepShed::add_sheep] for each [Sheep].
+    fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> Result<Vec<(Tattoo, AddSheepOutcome)>, errors::Error> {
+        Ok(sheeps
+            .into_iter()
+            .map(|sheep| {
+                let tattoo = sheep.tattoo.clone();
+                (tattoo, self.add_sheep(sheep).into())
+            })
+            .collect())
+    }
     /// Return the number of [Sheep] in the [SheepShed]
     fn sheep_count(&self) -> Result<usize, errors::Error>;
+    /// Return the [Sheep] with the given [Tattoo], or [None] if there is no such
+    /// [Sheep] in the [SheepShed]
+    fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, errors::Error>;
     /// Return an [Iterator] over all the [Sheep]s in the [SheepShed]
     fn sheep_iter(&self) -> Result<impl Iterator<Item = Sheep>, errors::Error>;
+    /// Return a page of at most `limit` [Sheep]s, starting after the given [Cursor]
+    /// or from the beginning if there is none.
+    ///
+    /// The [SheepPage] contains the [Cursor] to use to retrieve the next page, if any.
+    /// # Errors
+    /// Will return an [errors::Error::InvalidCursor] if the [Cursor] was not issued
+    /// by this [SheepShed] or has been tampered with.
+    fn list_page(&self, cursor: Option<&Cursor>, limit: usize) -> Result<SheepPage, errors::Error>;
+    /// Return the heaviest [Sheep] whose [Weight] matches the [WeightCriteria],
+    /// or [None] if no [Sheep] matches.
+    ///
+    /// The default implementation goes through all the [Sheep]s with [SheepShed::sheep_iter].
+    fn heaviest_matching(&self, criteria: WeightCriteria) -> Result<Option<Sheep>, errors::Error> {
+        Ok(self
+            .sheep_iter()?
+            .filter(|sheep| criteria.matches(&sheep.weight))
+            .max_by_key(|sheep| sheep.weight))
+    }
     /// Kill an unlucky Sheep.
-    /// Remove it from the [SheepShed] and return it's body.
+    /// Remove it from the [SheepShed] and return it's body, its weight history goes with it.
     /// # Errors
     /// It is not allowed to kill an inexistant [Sheep], will return an
     /// [errors::Error::SheepNotPresent] if the user tries to kill
     /// a [Sheep] that is not in the [SheepShed]
     fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, errors::Error>;
+    /// Kill the [Sheep] only if it was not modified since it was read, i.e. if
+    /// the stored [Sheep] still has the same `version`.
+    /// Remove it from the [SheepShed] and return it's body, its weight history goes with it.
+    /// # Errors
+    /// Will return an [errors::Error::ConcurrentModification] if the [Sheep] was
+    /// modified in the meantime, or an [errors::Error::SheepNotPresent] if it is
+    /// not in the [SheepShed] anymore.
+    fn kill_sheep_if_unchanged(&mut self, sheep: &Sheep) -> Result<Sheep, errors::Error>;
+    /// Weigh-in a Sheep.
+    /// Update the [Weight] of the [Sheep], record it in its history as a new
+    /// [WeighIn], increment its `version` and return the [Sheep] as it was before.
+    /// # Errors
+    /// It is not allowed to weigh an inexistant [Sheep], will return an
+    /// [errors::Error::SheepNotPresent] if the user tries to update
+    /// a [Sheep] that is not in the [SheepShed]
+    ///
+    /// Will return an [errors::Error::ConcurrentModification] if the [Sheep] was
+    /// modified by someone else during the weigh-in
+    ///
+    /// Will return an [errors::Error::InvalidWeight] if the [Weight] is not valid
+    /// (see [Weight::validate])
+    fn update_weight(&mut self, tattoo: &Tattoo, weight: Weight) -> Result<Sheep, errors::Error>;
+    /// Return the weight history of the [Sheep], oldest [WeighIn] first.
+    /// # Errors
+    /// Will return an [errors::Error::SheepNotPresent] if the [Sheep]
+    /// is not in the [SheepShed]
+    fn weight_history(&self, tattoo: &Tattoo) -> Result<Vec<WeighIn>, errors::Error>;
+    /// Transfer the [Sheep] with the given [Tattoo] from this [SheepShed] to `to`,
+    /// with its weight history, and return it as it is in `to`.
+    ///
+    /// The [Sheep] is never in both [SheepShed]s, nor in none of them, and its
+    /// `version` is reset to 0 in `to`.
+    /// # Errors
+    /// Will return an [errors::Error::SheepNotPresent] if the [Sheep] is not in
+    /// this [SheepShed], or an [errors::Error::SheepDuplicationError] if `to`
+    /// already has a [Sheep] with the same [Tattoo].
+    fn transfer_sheep(&mut self, to: &mut Self, tattoo: &Tattoo) -> Result<Sheep, errors::Error>;
+}
+
+/// The async twin of the [SheepShed] trait, for [SheepShed]s whose natural
+/// implementation is async (e.g. backed by a remote database).
+///
+/// Use [SyncAdapter] to expose an [AsyncSheepShed] as a [SheepShed] and
+/// [AsyncAdapter] to do the opposite.
+pub trait AsyncSheepShed {
+    /// Add a new [Sheep] in the [AsyncSheepShed]
+    /// # Errors
+    /// It is not allowed to add a duplicated [Sheep], will return an
+    /// [errors::Error::SheepDuplicationError] if the user tries to add
+    /// a [Sheep] with an already known [Tattoo]
+    ///
+    /// Will return an [errors::Error::InvalidWeight] if the [Sheep] is not valid
+    /// (see [Sheep::validate])
+    ///
+    /// The [Weight] of the [Sheep] is recorded as the first [WeighIn] of its history
+    /// and its `version` is reset to 0.
+    fn add_sheep(&mut self, sheep: Sheep)
+        -> impl Future<Output = Result<(), errors::Error>> + Send;
+    /// Add a batch of new [Sheep]s in the [AsyncSheepShed]
+    ///
+    /// Return the [AddSheepOutcome] of each [Sheep], in the same order as the batch.
+    /// A [Sheep] failing to be inserted does not prevent the others to be.
+    ///
+    /// The default implementation simply calls [AsyncSheepShed::add_sheep] for each [Sheep].
+    fn add_sheep_batch(
+        &mut self,
+        sheeps: Vec<Sheep>,
+    ) -> impl Future<Output = Result<Vec<(Tattoo, AddSheepOutcome)>, errors::Error>> + Send
+    where
+        Self: Send,
+    {
+        async move {
+            let mut outcomes = Vec::with_capacity(sheeps.len());
+            for sheep in sheeps {
+                let tattoo = sheep.tattoo.clone();
+                outcomes.push((tattoo, self.add_sheep(sheep).await.into()));
+            }
+            Ok(outcomes)
+        }
+    }
+    /// Return the number of [Sheep] in the [AsyncSheepShed]
+    fn sheep_count(&self) -> impl Future<Output = Result<usize, errors::Error>> + Send;
+    /// Return the [Sheep] with the given [Tattoo], or [None] if there is no such
+    /// [Sheep] in the [AsyncSheepShed]
+    fn get_sheep(
+        &self,
+        tattoo: &Tattoo,
+    ) -> impl Future<Output = Result<Option<Sheep>, errors::Error>> + Send;
+    /// Return an [Iterator] over all the [Sheep]s in the [AsyncSheepShed]
+    fn sheep_iter(
+        &self,
+    ) -> impl Future<Output = Result<impl Iterator<Item = Sheep> + Send, errors::Error>> + Send;
+    /// Return a [Stream] over all the [Sheep]s in the [AsyncSheepShed], yielding
+    /// them as soon as they are available instead of waiting for all of them.
+    fn sheep_stream(&self) -> impl Stream<Item = Result<Sheep, errors::Error>> + Send + '_;
+    /// Return a page of at most `limit` [Sheep]s, starting after the given [Cursor]
+    /// or from the beginning if there is none.
+    ///
+    /// The [SheepPage] contains the [Cursor] to use to retrieve the next page, if any.
+    /// # Errors
+    /// Will return an [errors::Error::InvalidCursor] if the [Cursor] was not issued
+    /// by this [AsyncSheepShed] or has been tampered with.
+    fn list_page(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> impl Future<Output = Result<SheepPage, errors::Error>> + Send;
+    /// Return the heaviest [Sheep] whose [Weight] matches the [WeightCriteria],
+    /// or [None] if no [Sheep] matches.
+    ///
+    /// The default implementation goes through all the [Sheep]s with
+    /// [AsyncSheepShed::sheep_stream], see [heaviest_in_stream].
+    fn heaviest_matching(
+        &self,
+        criteria: WeightCriteria,
+    ) -> impl Future<Output = Result<Option<Sheep>, errors::Error>> + Send
+    where
+        Self: Sync,
+    {
+        heaviest_in_stream(self, criteria)
+    }
+    /// Kill an unlucky Sheep.
+    /// Remove it from the [AsyncSheepShed] and return it's body, its weight history goes with it.
+    /// # Errors
+    /// It is not allowed to kill an inexistant [Sheep], will return an
+    /// [errors::Error::SheepNotPresent] if the user tries to kill
+    /// a [Sheep] that is not in the [AsyncSheepShed]
+    fn kill_sheep(
+        &mut self,
+        tattoo: &Tattoo,
+    ) -> impl Future<Output = Result<Sheep, errors::Error>> + Send;
+    /// Kill the [Sheep] only if it was not modified since it was read, i.e. if
+    /// the stored [Sheep] still has the same `version`.
+    /// Remove it from the [AsyncSheepShed] and return it's body, its weight history goes with it.
+    /// # Errors
+    /// Will return an [errors::Error::ConcurrentModification] if the [Sheep] was
+    /// modified in the meantime, or an [errors::Error::SheepNotPresent] if it is
+    /// not in the [AsyncSheepShed] anymore.
+    fn kill_sheep_if_unchanged(
+        &mut self,
+        sheep: &Sheep,
+    ) -> impl Future<Output = Result<Sheep, errors::Error>> + Send;
+    /// Weigh-in a Sheep.
+    /// Update the [Weight] of the [Sheep], record it in its history as a new
+    /// [WeighIn], increment its `version` and return the [Sheep] as it was before.
+    /// # Errors
+    /// It is not allowed to weigh an inexistant [Sheep], will return an
+    /// [errors::Error::SheepNotPresent] if the user tries to update
+    /// a [Sheep] that is not in the [AsyncSheepShed]
+    ///
+    /// Will return an [errors::Error::ConcurrentModification] if the [Sheep] was
+    /// modified by someone else during the weigh-in
+    ///
+    /// Will return an [errors::Error::InvalidWeight] if the [Weight] is not valid
+    /// (see [Weight::validate])
+    fn update_weight(
+        &mut self,
+        tattoo: &Tattoo,
+        weight: Weight,
+    ) -> impl Future<Output = Result<Sheep, errors::Error>> + Send;
+    /// Return the weight history of the [Sheep], oldest [WeighIn] first.
+    /// # Errors
+    /// Will return an [errors::Error::SheepNotPresent] if the [Sheep]
+    /// is not in the [AsyncSheepShed]
+    fn weight_history(
+        &self,
+        tattoo: &Tattoo,
+    ) -> impl Future<Output = Result<Vec<WeighIn>, errors::Error>> + Send;
+    /// See [SheepShed::transfer_sheep]
+    fn transfer_sheep(
+        &mut self,
+        to: &mut Self,
+        tattoo: &Tattoo,
+    ) -> impl Future<Output = Result<Sheep, errors::Error>> + Send;
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct MemorySheepShed(HashMap<Tattoo, Sheep>);
+pub struct MemorySheepShed {
+    sheeps: HashMap<Tattoo, Sheep>,
+    history: HashMap<Tattoo, Vec<WeighIn>>,
+}
+
+impl MemorySheepShed {
+    /// The key used to sign the [Cursor]s of a [MemorySheepShed]
+    const CURSOR_KEY: &'static [u8] = b"MemorySheepShed";
+}
 
 impl SheepShed for MemorySheepShed {
     fn add_sheep(&mut self, sheep: Sheep) -> Result<(), errors::Error> {
-        if self.0.contains_key(&sheep.tattoo) {
+        sheep.validate()?;
+        if self.sheeps.contains_key(&sheep.tattoo) {
             Err(errors::Error::SheepDuplicationError(sheep.tattoo))
         } else {
-            self.0.insert(sheep.tattoo.clone(), sheep);
+            self.history
+                .insert(sheep.tattoo.clone(), vec![WeighIn::now(sheep.weight)]);
+            self.sheeps.insert(
+                sheep.tattoo.clone(),
+                Sheep {
+                    version: 0,
+                    ..sheep
+                },
+            );
             Ok(())
         }
     }
@@ -42,20 +336,174 @@ impl SheepShed for MemorySheepShed {
     /// Return the number of [Sheep] in the [SheepShed]
     /// Never returns an [Err] variant.
     fn sheep_count(&self) -> Result<usize, errors::Error> {
-        Ok(self.0.len())
+        Ok(self.sheeps.len())
+    }
+
+    /// Never returns an [Err] variant.
+    fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, errors::Error> {
+        Ok(self.sheeps.get(tattoo).cloned())
     }
 
     fn sheep_iter(&self) -> Result<impl Iterator<Item = Sheep>, errors::Error> {
-        Ok(self.0.values().cloned())
+        Ok(self.sheeps.values().cloned())
+    }
+
+    /// The [Sheep]s are listed in [Tattoo] order, the [Cursor] holds the last
+    /// [Tattoo] of the page.
+    fn list_page(&self, cursor: Option<&Cursor>, limit: usize) -> Result<SheepPage, errors::Error> {
+        let start_after = cursor
+            .map(|c| {
+                let payload = c.open(Self::CURSOR_KEY)?;
+                String::from_utf8(payload)
+                    .ok()
+                    .and_then(|p| p.parse().ok())
+                    .ok_or_else(|| errors::Error::InvalidCursor(c.to_string()))
+            })
+            .transpose()?;
+        let mut tattoos = self
+            .sheeps
+            .keys()
+            .filter(|t| start_after.map_or(true, |sa| t.0 > sa))
+            .collect::<Vec<_>>();
+        tattoos.sort_by_key(|t| t.0);
+        let sheeps = tattoos
+            .iter()
+            .take(limit)
+            .map(|&t| self.sheeps[t].clone())
+            .collect::<Vec<_>>();
+        let next_cursor = if tattoos.len() > limit {
+            sheeps
+                .last()
+                .map(|s| Cursor::seal(s.tattoo.to_string().as_bytes(), Self::CURSOR_KEY))
+        } else {
+            None
+        };
+        Ok(SheepPage {
+            sheeps,
+            next_cursor,
+        })
     }
 
     fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, errors::Error> {
-        if self.0.contains_key(tattoo) {
-            Ok(self.0.remove(tattoo).unwrap())
+        if self.sheeps.contains_key(tattoo) {
+            self.history.remove(tattoo);
+            Ok(self.sheeps.remove(tattoo).unwrap())
+        } else {
+            Err(errors::Error::SheepNotPresent(tattoo.to_owned()))
+        }
+    }
+
+    fn kill_sheep_if_unchanged(&mut self, sheep: &Sheep) -> Result<Sheep, errors::Error> {
+        match self.sheeps.get(&sheep.tattoo) {
+            Some(stored) if stored.version == sheep.version => {
+                SheepShed::kill_sheep(self, &sheep.tattoo)
+            }
+            Some(_) => Err(errors::Error::ConcurrentModification(sheep.tattoo.clone())),
+            None => Err(errors::Error::SheepNotPresent(sheep.tattoo.clone())),
+        }
+    }
+
+    fn update_weight(&mut self, tattoo: &Tattoo, weight: Weight) -> Result<Sheep, errors::Error> {
+        weight.validate()?;
+        match self.sheeps.get_mut(tattoo) {
+            Some(sheep) => {
+                let previous = sheep.clone();
+                sheep.weight = weight;
+                sheep.version += 1;
+                self.history
+                    .entry(tattoo.clone())
+                    .or_default()
+                    .push(WeighIn::now(weight));
+                Ok(previous)
+            }
+            None => Err(errors::Error::SheepNotPresent(tattoo.to_owned())),
+        }
+    }
+
+    fn weight_history(&self, tattoo: &Tattoo) -> Result<Vec<WeighIn>, errors::Error> {
+        if self.sheeps.contains_key(tattoo) {
+            Ok(self.history.get(tattoo).cloned().unwrap_or_default())
         } else {
             Err(errors::Error::SheepNotPresent(tattoo.to_owned()))
         }
     }
+
+    fn transfer_sheep(&mut self, to: &mut Self, tattoo: &Tattoo) -> Result<Sheep, errors::Error> {
+        if !self.sheeps.contains_key(tattoo) {
+            return Err(errors::Error::SheepNotPresent(tattoo.to_owned()));
+        }
+        if to.sheeps.contains_key(tattoo) {
+            return Err(errors::Error::SheepDuplicationError(tattoo.to_owned()));
+        }
+        let sheep = Sheep {
+            version: 0,
+            ..self.sheeps.remove(tattoo).unwrap()
+        };
+        let history = self.history.remove(tattoo).unwrap_or_default();
+        to.history.insert(tattoo.clone(), history);
+        to.sheeps.insert(tattoo.clone(), sheep.clone());
+        Ok(sheep)
+    }
+}
+
+/// Nothing in a [MemorySheepShed] ever waits, so the async implementation
+/// simply resolves immediately with the result of its sync counterpart.
+impl AsyncSheepShed for MemorySheepShed {
+    async fn add_sheep(&mut self, sheep: Sheep) -> Result<(), errors::Error> {
+        SheepShed::add_sheep(self, sheep)
+    }
+
+    async fn sheep_count(&self) -> Result<usize, errors::Error> {
+        SheepShed::sheep_count(self)
+    }
+
+    async fn get_sheep(&self, tattoo: &Tattoo) -> Result<Option<Sheep>, errors::Error> {
+        SheepShed::get_sheep(self, tattoo)
+    }
+
+    async fn sheep_iter(&self) -> Result<impl Iterator<Item = Sheep> + Send, errors::Error> {
+        Ok(self.sheeps.values().cloned())
+    }
+
+    fn sheep_stream(&self) -> impl Stream<Item = Result<Sheep, errors::Error>> + Send + '_ {
+        stream::iter(self.sheeps.values().cloned().map(Ok))
+    }
+
+    async fn list_page(
+        &self,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<SheepPage, errors::Error> {
+        SheepShed::list_page(self, cursor, limit)
+    }
+
+    async fn kill_sheep(&mut self, tattoo: &Tattoo) -> Result<Sheep, errors::Error> {
+        SheepShed::kill_sheep(self, tattoo)
+    }
+
+    async fn kill_sheep_if_unchanged(&mut self, sheep: &Sheep) -> Result<Sheep, errors::Error> {
+        SheepShed::kill_sheep_if_unchanged(self, sheep)
+    }
+
+    async fn update_weight(
+        &mut self,
+        tattoo: &Tattoo,
+        weight: Weight,
+    ) -> Result<Sheep, errors::Error> {
+        SheepShed::update_weight(self, tattoo, weight)
+    }
+
+    async fn weight_history(&self, tattoo: &Tattoo) -> Result<Vec<WeighIn>, errors::Error> {
+        SheepShed::weight_history(self, tattoo)
+    }
+
+    async fn transfer_sheep(
+        &mut self,
+        to: &mut Self,
+        tattoo: &Tattoo,
+    ) -> Result<Sheep, errors::Error> {
+        SheepShed::transfer_sheep(self, to, tattoo)
+    }
 }
 
 #[cfg(test)]
@@ -77,45 +525,188 @@ mod tests {
     impl_test_template!(sheep_shed_sheep_count);
     impl_test_template!(sheep_shed_iterator);
     impl_test_template!(cannot_kill_inexistent_sheep);
+    impl_test_template!(get_sheep_by_tattoo);
+    impl_test_template!(sheep_weigh_in);
+    impl_test_template!(sheep_batch_insertion);
+    impl_test_template!(sheep_shed_pagination);
+    impl_test_template!(heaviest_matching_sheep);
+    impl_test_template!(invalid_weights_rejected);
+    impl_test_template!(sheep_details_preserved);
+    impl_test_template!(sheep_weight_history);
+    impl_test_template!(optimistic_concurrency);
+
+    #[test]
+    fn sheep_transfer() {
+        crate::test_templates::sheep_transfer(
+            MemorySheepShed::default(),
+            MemorySheepShed::default(),
+        )
+    }
+
+    #[test]
+    fn hunt_kills_the_heaviest_matching_sheep() {
+        futures::executor::block_on(async {
+            let mut sheep_shed = MemorySheepShed::default();
+            for (tattoo, ug) in [
+                (1, 100_000_000_003),
+                (2, 120_000_000_007),
+                (3, 120_000_000_008),
+            ] {
+                AsyncSheepShed::add_sheep(
+                    &mut sheep_shed,
+                    Sheep::try_new(Tattoo(tattoo), Weight::from_ug(ug)).unwrap(),
+                )
+                .await
+                .unwrap();
+            }
+            for expected in [Some(Tattoo(2)), Some(Tattoo(1)), None] {
+                let hunted = hunt(&mut sheep_shed, WeightCriteria::PrimeUg, 3)
+                    .await
+                    .unwrap();
+                assert_eq!(hunted.map(|sheep| sheep.tattoo), expected);
+            }
+            assert_eq!(AsyncSheepShed::sheep_count(&sheep_shed).await.unwrap(), 1);
+        })
+    }
+
+    // Same templates, but going through the AsyncSheepShed implementation of
+    // MemorySheepShed (SyncAdapter) and through both adapters back-to-back.
+    // The SyncAdapter expects to be called from a blocking thread of a tokio
+    // runtime, so we simulate that like the DynamoDBSheepShed tests do.
+    fn run_blocking<F: FnOnce() + Send + 'static>(f: F) {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async { rt.spawn_blocking(f).await.unwrap() })
+    }
+
+    macro_rules! impl_async_test_template {
+        ($tn: tt) => {
+            mod $tn {
+                use super::*;
+
+                #[test]
+                fn async_sheep_shed() {
+                    run_blocking(|| {
+                        let sheep_shed = SyncAdapter(MemorySheepShed::default());
+                        crate::test_templates::$tn(sheep_shed)
+                    })
+                }
+
+                #[test]
+                fn adapters_round_trip() {
+                    run_blocking(|| {
+                        let sheep_shed = SyncAdapter(AsyncAdapter(MemorySheepShed::default()));
+                        crate::test_templates::$tn(sheep_shed)
+                    })
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn sheep_shed_stream() {
+        futures::executor::block_on(crate::test_templates::sheep_shed_stream(
+            MemorySheepShed::default(),
+        ))
+    }
+
+    #[test]
+    fn adapter_sheep_shed_stream() {
+        futures::executor::block_on(crate::test_templates::sheep_shed_stream(AsyncAdapter(
+            MemorySheepShed::default(),
+        )))
+    }
+
+    mod async_flavour {
+        use super::*;
+
+        impl_async_test_template!(cannot_duplicate_sheep);
+        impl_async_test_template!(sheep_shed_sheep_count);
+        impl_async_test_template!(sheep_shed_iterator);
+        impl_async_test_template!(cannot_kill_inexistent_sheep);
+        impl_async_test_template!(get_sheep_by_tattoo);
+        impl_async_test_template!(sheep_weigh_in);
+        impl_async_test_template!(sheep_batch_insertion);
+        impl_async_test_template!(sheep_shed_pagination);
+        impl_async_test_template!(heaviest_matching_sheep);
+        impl_async_test_template!(invalid_weights_rejected);
+        impl_async_test_template!(sheep_details_preserved);
+        impl_async_test_template!(sheep_weight_history);
+        impl_async_test_template!(optimistic_concurrency);
+
+        mod sheep_transfer {
+            use super::*;
+
+            #[test]
+            fn async_sheep_shed() {
+                run_blocking(|| {
+                    crate::test_templates::sheep_transfer(
+                        SyncAdapter(MemorySheepShed::default()),
+                        SyncAdapter(MemorySheepShed::default()),
+                    )
+                })
+            }
+
+            #[test]
+            fn adapters_round_trip() {
+                run_blocking(|| {
+                    crate::test_templates::sheep_transfer(
+                        SyncAdapter(AsyncAdapter(MemorySheepShed::default())),
+                        SyncAdapter(AsyncAdapter(MemorySheepShed::default())),
+                    )
+                })
+            }
+        }
+    }
 }
 
 #[cfg(any(feature = "sheepshed_tests", test))]
 pub mod test_templates {
-    use crate::{errors::Error, sheep::WeightUnit, Sheep, SheepShed, Tattoo, Weight};
+    use futures::TryStreamExt;
+
+    use crate::{
+        errors::Error, sheep::WeightUnit, AddSheepOutcome, AsyncSheepShed, Cursor, Sex, Sheep,
+        SheepShed, Tattoo, Weight, WeightCriteria,
+    };
+
+    fn base_sheeps() -> [Sheep; 2] {
+        let sheep1 =
+            Sheep::try_new(Tattoo(1), Weight::from_unit(100.0, WeightUnit::Kilograms)).unwrap();
+        let sheep2 =
+            Sheep::try_new(Tattoo(2), Weight::from_unit(120.0, WeightUnit::Kilograms)).unwrap();
+        [sheep1, sheep2]
+    }
 
     fn prep_base_sheep_shed<T: SheepShed>(mut sheep_shed: T) -> T {
-        let sheep1 = Sheep {
-            tattoo: Tattoo(1),
-            weight: Weight::from_unit(100.0, WeightUnit::Kilograms),
-        };
-        let sheep2 = Sheep {
-            tattoo: Tattoo(2),
-            weight: Weight::from_unit(120.0, WeightUnit::Kilograms),
-        };
-        sheep_shed.add_sheep(sheep1).unwrap();
-        sheep_shed.add_sheep(sheep2).unwrap();
+        for sheep in base_sheeps() {
+            sheep_shed.add_sheep(sheep).unwrap();
+        }
+        sheep_shed
+    }
+
+    async fn prep_base_async_sheep_shed<T: AsyncSheepShed>(mut sheep_shed: T) -> T {
+        for sheep in base_sheeps() {
+            sheep_shed.add_sheep(sheep).await.unwrap();
+        }
         sheep_shed
     }
 
     pub fn cannot_duplicate_sheep<T: SheepShed>(sheep_shed: T) {
         let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
-        let sheep3 = Sheep {
-            tattoo: Tattoo(1),
-            weight: Weight::from_unit(120.0, WeightUnit::Kilograms),
-        };
+        let sheep3 =
+            Sheep::try_new(Tattoo(1), Weight::from_unit(120.0, WeightUnit::Kilograms)).unwrap();
         // Sheep3 has the same Tattoo as Sheep1 so it should fail
-        assert!(sheep_shed.add_sheep(sheep3).is_err_and(|e| match e {
-            Error::SheepDuplicationError(_) => true,
-            _ => false,
-        }));
+        assert!(sheep_shed
+            .add_sheep(sheep3)
+            .is_err_and(|e| matches!(e, Error::SheepDuplicationError(_))));
     }
 
     pub fn sheep_shed_sheep_count<T: SheepShed>(sheep_shed: T) {
         let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
-        let sheep3 = Sheep {
-            tattoo: Tattoo(4),
-            weight: Weight::from_unit(120.0, WeightUnit::Kilograms),
-        };
+        let sheep3 =
+            Sheep::try_new(Tattoo(4), Weight::from_unit(120.0, WeightUnit::Kilograms)).unwrap();
         assert_eq!(sheep_shed.sheep_count().unwrap(), 2);
         sheep_shed.add_sheep(sheep3).unwrap();
         assert_eq!(sheep_shed.sheep_count().unwrap(), 3);
@@ -133,16 +724,383 @@ pub mod test_templates {
     pub fn cannot_kill_inexistent_sheep<T: SheepShed>(sheep_shed: T) {
         let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
         // Inexistant tattoo
-        assert!(sheep_shed.kill_sheep(&Tattoo(4)).is_err_and(|e| match e {
-            Error::SheepNotPresent(_) => true,
-            _ => false,
-        }));
+        assert!(sheep_shed
+            .kill_sheep(&Tattoo(4))
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
         // Existing tattoo
         assert!(sheep_shed.kill_sheep(&Tattoo(2)).is_ok());
         // Not anymore
-        assert!(sheep_shed.kill_sheep(&Tattoo(2)).is_err_and(|e| match e {
-            Error::SheepNotPresent(_) => true,
-            _ => false,
-        }));
+        assert!(sheep_shed
+            .kill_sheep(&Tattoo(2))
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+    }
+
+    pub fn get_sheep_by_tattoo<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let sheep = sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        assert_eq!(sheep.tattoo, Tattoo(1));
+        assert_eq!(
+            sheep.weight,
+            Weight::from_unit(100.0, WeightUnit::Kilograms)
+        );
+        // Inexistant tattoo
+        assert!(sheep_shed.get_sheep(&Tattoo(4)).unwrap().is_none());
+        // Not anymore
+        sheep_shed.kill_sheep(&Tattoo(1)).unwrap();
+        assert!(sheep_shed.get_sheep(&Tattoo(1)).unwrap().is_none());
+    }
+
+    pub fn sheep_weigh_in<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let new_weight = Weight::from_unit(110.0, WeightUnit::Kilograms);
+        let previous = sheep_shed.update_weight(&Tattoo(1), new_weight).unwrap();
+        assert_eq!(previous.tattoo, Tattoo(1));
+        assert_eq!(
+            previous.weight,
+            Weight::from_unit(100.0, WeightUnit::Kilograms)
+        );
+        let sheep = sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        assert_eq!(sheep.weight, new_weight);
+        // The flock is still the same
+        assert_eq!(sheep_shed.sheep_count().unwrap(), 2);
+        // Inexistant tattoo
+        assert!(sheep_shed
+            .update_weight(&Tattoo(4), new_weight)
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+        assert!(sheep_shed.get_sheep(&Tattoo(4)).unwrap().is_none());
+    }
+
+    pub fn sheep_batch_insertion<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let batch = [3, 1, 4, 3]
+            .into_iter()
+            .map(|t| {
+                Sheep::try_new(Tattoo(t), Weight::from_unit(90.0, WeightUnit::Kilograms)).unwrap()
+            })
+            .collect();
+        let outcomes = sheep_shed.add_sheep_batch(batch).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                (Tattoo(3), AddSheepOutcome::Inserted),
+                // Already in the shed
+                (Tattoo(1), AddSheepOutcome::Duplicate),
+                (Tattoo(4), AddSheepOutcome::Inserted),
+                // Already earlier in the batch
+                (Tattoo(3), AddSheepOutcome::Duplicate),
+            ]
+        );
+        assert_eq!(sheep_shed.sheep_count().unwrap(), 4);
+        // The original sheep was not overwritten
+        assert_eq!(
+            sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap().weight,
+            Weight::from_unit(100.0, WeightUnit::Kilograms)
+        );
+    }
+
+    pub fn sheep_shed_pagination<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        for t in 3..=5 {
+            sheep_shed
+                .add_sheep(
+                    Sheep::try_new(Tattoo(t), Weight::from_unit(90.0, WeightUnit::Kilograms))
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+        let mut tattoos = vec![];
+        let mut cursor = None;
+        let mut first_cursor = None;
+        loop {
+            let page = sheep_shed.list_page(cursor.as_ref(), 2).unwrap();
+            assert!(page.sheeps.len() <= 2);
+            // Only the last page may be short
+            assert!(page.next_cursor.is_none() || page.sheeps.len() == 2);
+            tattoos.extend(page.sheeps.into_iter().map(|s| s.tattoo.0));
+            if first_cursor.is_none() {
+                first_cursor.clone_from(&page.next_cursor);
+            }
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+        tattoos.sort();
+        assert_eq!(tattoos, vec![1, 2, 3, 4, 5]);
+
+        // A forged cursor is rejected
+        let forged: Cursor = format!("{}x", first_cursor.unwrap()).parse().unwrap();
+        assert!(sheep_shed
+            .list_page(Some(&forged), 2)
+            .is_err_and(|e| matches!(e, Error::InvalidCursor(_))));
+    }
+
+    pub fn heaviest_matching_sheep<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let heaviest_tattoo = |sheep_shed: &T, criteria| {
+            sheep_shed
+                .heaviest_matching(criteria)
+                .unwrap()
+                .map(|sheep| sheep.tattoo)
+        };
+        // None of the base sheeps has a prime weight
+        assert_eq!(heaviest_tattoo(&sheep_shed, WeightCriteria::PrimeUg), None);
+        assert_eq!(
+            heaviest_tattoo(&sheep_shed, WeightCriteria::Any),
+            Some(Tattoo(2))
+        );
+        for (tattoo, weight) in [
+            (3, 100_000_000_003),
+            (4, 110_000_000_003),
+            (5, 115_000_000_000),
+        ] {
+            sheep_shed
+                .add_sheep(Sheep::try_new(Tattoo(tattoo), Weight::from_ug(weight)).unwrap())
+                .unwrap();
+        }
+        assert_eq!(
+            heaviest_tattoo(&sheep_shed, WeightCriteria::PrimeUg),
+            Some(Tattoo(4))
+        );
+        // Weigh-ins must be taken into account
+        sheep_shed
+            .update_weight(&Tattoo(1), Weight::from_ug(120_000_000_007))
+            .unwrap();
+        sheep_shed
+            .update_weight(&Tattoo(4), Weight::from_ug(110_000_000_000))
+            .unwrap();
+        assert_eq!(
+            heaviest_tattoo(&sheep_shed, WeightCriteria::PrimeUg),
+            Some(Tattoo(1))
+        );
+        assert_eq!(
+            heaviest_tattoo(&sheep_shed, WeightCriteria::Any),
+            Some(Tattoo(1))
+        );
+    }
+
+    pub fn invalid_weights_rejected<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let too_light = Weight::from_ug(Weight::MIN.as_ug() - 1);
+        let too_heavy = Weight::from_ug(Weight::MAX.as_ug() + 1);
+        assert!(sheep_shed
+            .add_sheep(Sheep {
+                weight: Weight::ZERO,
+                ..Sheep::try_new(Tattoo(3), Weight::MIN).unwrap()
+            })
+            .is_err_and(|e| matches!(e, Error::InvalidWeight(_))));
+        assert!(sheep_shed
+            .update_weight(&Tattoo(1), too_heavy)
+            .is_err_and(|e| matches!(e, Error::InvalidWeight(_))));
+        assert_eq!(
+            sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap().weight,
+            Weight::from_unit(100.0, WeightUnit::Kilograms)
+        );
+        let outcomes = sheep_shed
+            .add_sheep_batch(vec![
+                Sheep {
+                    weight: too_light,
+                    ..Sheep::try_new(Tattoo(3), Weight::MIN).unwrap()
+                },
+                Sheep::try_new(Tattoo(4), Weight::MIN).unwrap(),
+            ])
+            .unwrap();
+        assert!(matches!(
+            outcomes[0],
+            (Tattoo(3), AddSheepOutcome::Failed { .. })
+        ));
+        assert_eq!(outcomes[1], (Tattoo(4), AddSheepOutcome::Inserted));
+        assert_eq!(sheep_shed.sheep_count().unwrap(), 3);
+        assert!(sheep_shed.get_sheep(&Tattoo(3)).unwrap().is_none());
+    }
+
+    pub fn sheep_details_preserved<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let dolly = Sheep {
+            name: Some("Dolly".to_owned()),
+            breed: Some("Finn Dorset".to_owned()),
+            birth_date: chrono::NaiveDate::from_ymd_opt(1996, 7, 5),
+            sex: Some(Sex::Ewe),
+            tags: vec!["clone".to_owned(), "famous".to_owned()],
+            ..Sheep::try_new(Tattoo(3), Weight::from_unit(90.0, WeightUnit::Kilograms)).unwrap()
+        };
+        sheep_shed.add_sheep(dolly.clone()).unwrap();
+        let stored = sheep_shed.get_sheep(&Tattoo(3)).unwrap().unwrap();
+        assert_eq!(stored.name, dolly.name);
+        assert_eq!(stored.breed, dolly.breed);
+        assert_eq!(stored.birth_date, dolly.birth_date);
+        assert_eq!(stored.sex, dolly.sex);
+        assert_eq!(stored.tags, dolly.tags);
+        // A weigh-in keeps the details
+        let new_weight = Weight::from_unit(95.0, WeightUnit::Kilograms);
+        sheep_shed.update_weight(&Tattoo(3), new_weight).unwrap();
+        let stored = sheep_shed.get_sheep(&Tattoo(3)).unwrap().unwrap();
+        assert_eq!(stored.weight, new_weight);
+        assert_eq!(stored.name, dolly.name);
+        assert_eq!(stored.tags, dolly.tags);
+        // The base sheeps have no details
+        let base = sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        assert_eq!(base.name, None);
+        assert!(base.tags.is_empty());
+    }
+
+    pub fn sheep_weight_history<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let history_weights = |sheep_shed: &T, tattoo| {
+            let history = sheep_shed.weight_history(&Tattoo(tattoo)).unwrap();
+            // Oldest first
+            assert!(history
+                .windows(2)
+                .all(|w| w[0].weighed_at <= w[1].weighed_at));
+            history.into_iter().map(|w| w.weight).collect::<Vec<_>>()
+        };
+        // Adding a sheep records its first weigh-in
+        assert_eq!(
+            history_weights(&sheep_shed, 1),
+            vec![Weight::from_unit(100.0, WeightUnit::Kilograms)]
+        );
+        let weights = [95.0, 102.5, 98.0].map(|kg| Weight::from_unit(kg, WeightUnit::Kilograms));
+        for weight in weights {
+            sheep_shed.update_weight(&Tattoo(1), weight).unwrap();
+        }
+        let mut expected = vec![Weight::from_unit(100.0, WeightUnit::Kilograms)];
+        expected.extend(weights);
+        assert_eq!(history_weights(&sheep_shed, 1), expected);
+        // The other sheeps histories are untouched
+        assert_eq!(
+            history_weights(&sheep_shed, 2),
+            vec![Weight::from_unit(120.0, WeightUnit::Kilograms)]
+        );
+        // A rejected weigh-in is not recorded
+        assert!(sheep_shed
+            .update_weight(&Tattoo(1), Weight::ZERO)
+            .is_err_and(|e| matches!(e, Error::InvalidWeight(_))));
+        assert_eq!(history_weights(&sheep_shed, 1).len(), 4);
+        // Batch insertion records the first weigh-in too
+        sheep_shed
+            .add_sheep_batch(vec![Sheep::try_new(Tattoo(3), Weight::MIN).unwrap()])
+            .unwrap();
+        assert_eq!(history_weights(&sheep_shed, 3), vec![Weight::MIN]);
+        // Inexistant tattoo
+        assert!(sheep_shed
+            .weight_history(&Tattoo(4))
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+        // The history dies with the sheep, a new sheep with the same tattoo starts afresh
+        sheep_shed.kill_sheep(&Tattoo(1)).unwrap();
+        assert!(sheep_shed
+            .weight_history(&Tattoo(1))
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+        sheep_shed
+            .add_sheep(Sheep::try_new(Tattoo(1), Weight::MIN).unwrap())
+            .unwrap();
+        assert_eq!(history_weights(&sheep_shed, 1), vec![Weight::MIN]);
+    }
+
+    pub fn optimistic_concurrency<T: SheepShed>(sheep_shed: T) {
+        let mut sheep_shed = prep_base_sheep_shed(sheep_shed);
+        let read = sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        assert_eq!(read.version, 0);
+        // Each weigh-in makes a new version
+        let previous = sheep_shed
+            .update_weight(&Tattoo(1), Weight::from_unit(105.0, WeightUnit::Kilograms))
+            .unwrap();
+        assert_eq!(previous.version, 0);
+        let current = sheep_shed.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        assert_eq!(current.version, 1);
+        // The sheep changed since it was read, it escapes
+        assert!(sheep_shed
+            .kill_sheep_if_unchanged(&read)
+            .is_err_and(|e| matches!(e, Error::ConcurrentModification(_))));
+        assert!(sheep_shed.get_sheep(&Tattoo(1)).unwrap().is_some());
+        // Up-to-date, it does not
+        let killed = sheep_shed.kill_sheep_if_unchanged(&current).unwrap();
+        assert_eq!(killed.tattoo, Tattoo(1));
+        assert_eq!(killed.version, 1);
+        assert!(sheep_shed
+            .kill_sheep_if_unchanged(&current)
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+        assert_eq!(sheep_shed.sheep_count().unwrap(), 1);
+        // The version of a new sheep always starts at 0
+        sheep_shed
+            .add_sheep(Sheep {
+                version: 42,
+                ..Sheep::try_new(Tattoo(3), Weight::MIN).unwrap()
+            })
+            .unwrap();
+        assert_eq!(
+            sheep_shed.get_sheep(&Tattoo(3)).unwrap().unwrap().version,
+            0
+        );
+    }
+
+    pub fn sheep_transfer<T: SheepShed>(from: T, to: T) {
+        let mut from = prep_base_sheep_shed(from);
+        let mut to = to;
+        let resident = Sheep::try_new(Tattoo(2), Weight::MIN).unwrap();
+        to.add_sheep(resident.clone()).unwrap();
+        let weight = Weight::from_unit(105.0, WeightUnit::Kilograms);
+        from.update_weight(&Tattoo(1), weight).unwrap();
+        let history = from.weight_history(&Tattoo(1)).unwrap();
+        assert_eq!(history.len(), 2);
+
+        // The sheep moves with its history and a fresh version
+        let transferred = from.transfer_sheep(&mut to, &Tattoo(1)).unwrap();
+        assert_eq!(transferred.tattoo, Tattoo(1));
+        assert_eq!(transferred.weight, weight);
+        assert_eq!(transferred.version, 0);
+        assert!(from.get_sheep(&Tattoo(1)).unwrap().is_none());
+        assert!(from
+            .weight_history(&Tattoo(1))
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+        let stored = to.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        assert_eq!((stored.weight, stored.version), (weight, 0));
+        assert_eq!(to.weight_history(&Tattoo(1)).unwrap(), history);
+        assert_eq!(from.sheep_count().unwrap(), 1);
+        assert_eq!(to.sheep_count().unwrap(), 2);
+
+        // It is not in the source anymore
+        assert!(from
+            .transfer_sheep(&mut to, &Tattoo(1))
+            .is_err_and(|e| matches!(e, Error::SheepNotPresent(_))));
+        // The destination already has a sheep with this tattoo, nothing moves
+        assert!(from
+            .transfer_sheep(&mut to, &Tattoo(2))
+            .is_err_and(|e| matches!(e, Error::SheepDuplicationError(_))));
+        assert_eq!(from.sheep_count().unwrap(), 1);
+        assert_eq!(
+            to.get_sheep(&Tattoo(2)).unwrap().unwrap().weight,
+            resident.weight
+        );
+        assert_eq!(from.weight_history(&Tattoo(2)).unwrap().len(), 1);
+
+        // The transferred sheep lives its life in its new shed
+        to.update_weight(&Tattoo(1), Weight::MIN).unwrap();
+        assert_eq!(to.weight_history(&Tattoo(1)).unwrap().len(), 3);
+        let current = to.get_sheep(&Tattoo(1)).unwrap().unwrap();
+        to.kill_sheep_if_unchanged(&current).unwrap();
+        // Transfers work both ways
+        assert!(to
+            .transfer_sheep(&mut from, &Tattoo(2))
+            .is_err_and(|e| matches!(e, Error::SheepDuplicationError(_))));
+        from.kill_sheep(&Tattoo(2)).unwrap();
+        to.transfer_sheep(&mut from, &Tattoo(2)).unwrap();
+        assert_eq!(
+            from.get_sheep(&Tattoo(2)).unwrap().unwrap().weight,
+            Weight::MIN
+        );
+        assert_eq!(to.sheep_count().unwrap(), 0);
+    }
+
+    pub async fn sheep_shed_stream<T: AsyncSheepShed>(sheep_shed: T) {
+        let sheep_shed = prep_base_async_sheep_shed(sheep_shed).await;
+        let weight = sheep_shed
+            .sheep_stream()
+            .try_fold(
+                Weight::ZERO,
+                |acc, sheep| async move { Ok(acc + sheep.weight) },
+            )
+            .await
+            .unwrap();
+        assert_eq!(weight, Weight::from_unit(220.0, WeightUnit::Kilograms));
     }
 }
@@ -0,0 +1,322 @@
+//! Primality test for [u64] values.
+//!
+//! [is_prime] is a deterministic Miller-Rabin: testing the first 12 primes as
+//! witnesses is enough to decide the primality of every number below 3.3 * 10^24,
+//! and therefore of every [u64].
+//!
+//! [sieve_of_eratosthenes] lists the small primes, [is_prime_weight] uses them
+//! for trial division before falling back to [is_prime].
+
+use crate::Weight;
+
+/// The witnesses that make Miller-Rabin deterministic for every [u64]
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Compute `a * b mod m` without overflowing
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+/// Compute `base ^ exp mod m` by square-and-multiply
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Return `true` if `n` is a prime number
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    // Small primes are their own witnesses, and their multiples are quickly ruled out
+    for p in WITNESSES {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+    // Write n - 1 as d * 2^s with d odd
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'witness: for a in WITNESSES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        // a is a witness of the compositeness of n
+        return false;
+    }
+    true
+}
+
+/// Create a Sieve of Eratostenes containing all the primes between 0 and n
+pub fn sieve_of_eratosthenes(n: u64) -> Vec<u64> {
+    assert!(n < usize::MAX as u64);
+    if n < 2 {
+        return vec![];
+    }
+    // Boolean array with a value for every number from 0 to n
+    // Initially every number from 0 to n is considered prime
+    // So the array is initialized at "true" for every index
+    let mut tmp = vec![true; n as usize + 1];
+    // 0 and 1 are not primes
+    tmp[0] = false;
+    tmp[1] = false;
+    // Compute the square root of n, rounding up
+    let sqrt_n = (n as f64).sqrt() as usize + 1;
+    // Cast n to an usize instead of a u64
+    let n_usize = n as usize;
+
+    // For every candidate i from 2 to SquareRoot(n) rounded up excluded
+    for i in 2..sqrt_n {
+        // If the candidate i is prime
+        // Exemple1: i = 2
+        // Exemple2: i = 3
+        if tmp[i] {
+            // Then initialize j = i^2, this optimization work because of maths:
+            // any multiple of our prime "i" that is inferior to i^2 MUST BE
+            // a multiple of a previously processed prime, so already marked false.
+            // When we process multiples of 3, we start at 9, skipping 6,
+            // but 6 is 2*3 and was already taken care of when processing multiples of 2.
+            // Exemple1: j = 4
+            // Exemple2: j = 9
+            let mut j = i * i;
+            // As long as j is <= n
+            while j <= n_usize {
+                // Mark every j as "not prime"
+                // Exemple1: 4, 6, 8, etc...
+                // Exemple2: 9, 12, 15, 18, etc...
+                tmp[j] = false;
+                // Increment j by i
+                // Exemple1: j += 2
+                // Exemple2: j += 3
+                j += i;
+            }
+        }
+    }
+    // At this point:
+    // tmp[i] = true if i is prime
+    // tmp[i] = false if i is NOT prime
+    // Iterate over tmp to extract our sieve
+    tmp.into_iter()
+        // Enumerate provide the index alongside
+        // the corresponding boolean value
+        .enumerate()
+        // We "filter" to keep only the prime indexes
+        .filter(|(_index, is_prime)| *is_prime)
+        // Indexes are of type usize but we want u64
+        // So we "map" the values
+        .map(|(index, _is_prime)| index as u64)
+        // We collect
+        .collect()
+}
+
+/// Return `true` if the [Weight] expressed in micro-grams is a prime number, using
+/// the `sieve` produced by [sieve_of_eratosthenes] for trial division.
+///
+/// If the `sieve` does not contain all the primes up to the square root of the
+/// [Weight], trial division cannot decide and [Weight::is_prime_ug] is used instead.
+pub fn is_prime_weight(weight: Weight, sieve: &[u64]) -> bool {
+    let weight_ug = weight.as_ug();
+    if weight_ug < 2 {
+        return false;
+    }
+    for &prime in sieve {
+        // Equivalent to prime * prime > weight_ug without the overflow
+        if prime > weight_ug / prime {
+            return true;
+        }
+        if weight_ug % prime == 0 {
+            // A prime is only divisible by itself
+            return weight_ug == prime;
+        }
+    }
+    weight.is_prime_ug()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_numbers() {
+        let primes = [2, 3, 5, 7, 11, 13, 37, 41, 97, 7919];
+        let not_primes = [0, 1, 4, 6, 9, 15, 25, 49, 1369, 7917];
+        for p in primes {
+            assert!(is_prime(p), "{p} is prime");
+        }
+        for np in not_primes {
+            assert!(!is_prime(np), "{np} is not prime");
+        }
+    }
+
+    #[test]
+    fn pseudoprimes() {
+        // Carmichael numbers and strong pseudoprimes to the first bases
+        let not_primes = [
+            561,
+            1_105,
+            2_047,
+            1_373_653,
+            25_326_001,
+            3_215_031_751,
+            2_152_302_898_747,
+            3_474_749_660_383,
+            341_550_071_728_321,
+            3_825_123_056_546_413_051,
+        ];
+        for np in not_primes {
+            assert!(!is_prime(np), "{np} is not prime");
+        }
+    }
+
+    #[test]
+    fn large_numbers() {
+        // Largest u64 prime and the square of the largest u32 prime
+        assert!(is_prime(18_446_744_073_709_551_557));
+        assert!(!is_prime(u64::MAX));
+        assert!(!is_prime(4_294_967_291 * 4_294_967_291));
+        assert!(is_prime(100_000_000_003));
+        assert!(is_prime(120_000_000_007));
+        assert!(!is_prime(120_000_000_000));
+    }
+
+    #[test]
+    fn small_sieve_of_eratosthenes() {
+        let soe = sieve_of_eratosthenes(1000);
+        let primes_to_1000 = vec![
+            2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83,
+            89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179,
+            181, 191, 193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271,
+            277, 281, 283, 293, 307, 311, 313, 317, 331, 337, 347, 349, 353, 359, 367, 373, 379,
+            383, 389, 397, 401, 409, 419, 421, 431, 433, 439, 443, 449, 457, 461, 463, 467, 479,
+            487, 491, 499, 503, 509, 521, 523, 541, 547, 557, 563, 569, 571, 577, 587, 593, 599,
+            601, 607, 613, 617, 619, 631, 641, 643, 647, 653, 659, 661, 673, 677, 683, 691, 701,
+            709, 719, 727, 733, 739, 743, 751, 757, 761, 769, 773, 787, 797, 809, 811, 821, 823,
+            827, 829, 839, 853, 857, 859, 863, 877, 881, 883, 887, 907, 911, 919, 929, 937, 941,
+            947, 953, 967, 971, 977, 983, 991, 997,
+        ];
+        assert_eq!(soe, primes_to_1000);
+    }
+
+    #[test]
+    fn is_prime_101() {
+        let soe = sieve_of_eratosthenes(101);
+        assert_eq!(soe.last().cloned().unwrap(), 101);
+    }
+
+    #[test]
+    fn sieve_0() {
+        let soe = sieve_of_eratosthenes(0);
+        assert_eq!(soe, Vec::<u64>::new());
+    }
+
+    #[test]
+    fn sieve_1() {
+        let soe = sieve_of_eratosthenes(1);
+        assert_eq!(soe, Vec::<u64>::new());
+    }
+
+    #[test]
+    fn sieve_2() {
+        let soe = sieve_of_eratosthenes(2);
+        assert_eq!(soe, vec![2]);
+    }
+
+    #[test]
+    fn is_not_prime_102() {
+        let soe = sieve_of_eratosthenes(102);
+        assert_eq!(soe.last().cloned().unwrap(), 101);
+    }
+
+    #[test]
+    fn prime_weight_boundaries() {
+        let soe = sieve_of_eratosthenes(1000);
+        let is_prime = |ug| is_prime_weight(Weight::from_ug(ug), &soe);
+        assert!(!is_prime(0));
+        assert!(!is_prime(1));
+        assert!(is_prime(2));
+        assert!(!is_prime(4));
+        // Primes inside the sieve are not rejected because they divide themselves
+        for &p in &soe {
+            assert!(is_prime(p), "{p} is prime");
+        }
+        // Primes and composites outside the sieve but below the square of its largest prime
+        assert!(is_prime(1009));
+        assert!(is_prime(993_997));
+        assert!(!is_prime(1001));
+        // Square of the largest prime of the sieve
+        assert!(!is_prime(997 * 997));
+        // Beyond the reach of the sieve
+        assert!(!is_prime(1009 * 1009));
+        assert!(!is_prime(1009 * 1013));
+        assert!(is_prime(100_000_000_003));
+        assert!(!is_prime(4_294_967_291 * 4_294_967_291));
+        assert!(is_prime(18_446_744_073_709_551_557));
+        assert!(!is_prime(u64::MAX));
+        // An empty sieve cannot decide anything on its own
+        assert!(is_prime_weight(Weight::from_ug(7919), &[]));
+        assert!(!is_prime_weight(Weight::from_ug(7917), &[]));
+    }
+
+    #[test]
+    fn prime_weight_with_wolf_sieve() {
+        let sieve_max = (Weight::MAX.as_ug() as f64).sqrt() as u64;
+        let soe = sieve_of_eratosthenes(sieve_max);
+        let largest = *soe.last().unwrap();
+        let is_prime = |ug| is_prime_weight(Weight::from_ug(ug), &soe);
+        assert!(is_prime(largest));
+        assert!(!is_prime(largest * largest));
+        assert!(!is_prime(largest * soe[soe.len() - 2]));
+        // Heavier than the square of the largest prime of the sieve
+        assert!(is_prime(160_000_000_003));
+        assert!(!is_prime(160_000_000_001));
+        assert!(is_prime(120_000_000_007));
+        assert!(!is_prime(120_000_000_000));
+    }
+
+    #[test]
+    fn prime_weight_agrees_with_sieve() {
+        const MAX: u64 = 100_000;
+        // The small sieve only decides weights up to 223^2, larger ones exercise the fallback
+        let small_soe = sieve_of_eratosthenes(223);
+        let soe = sieve_of_eratosthenes(MAX);
+        let mut primes = soe.iter().peekable();
+        for n in 0..=MAX {
+            let in_sieve = primes.next_if_eq(&&n).is_some();
+            assert_eq!(
+                is_prime_weight(Weight::from_ug(n), &small_soe),
+                in_sieve,
+                "is_prime_weight disagrees with the sieve for {n}"
+            );
+        }
+    }
+
+    #[test]
+    fn is_prime_agrees_with_sieve() {
+        const MAX: u64 = 5_000_000;
+        let soe = sieve_of_eratosthenes(MAX);
+        let mut primes = soe.iter().peekable();
+        for n in 0..=MAX {
+            let in_sieve = primes.next_if_eq(&&n).is_some();
+            assert_eq!(
+                is_prime(n),
+                in_sieve,
+                "is_prime disagrees with the sieve for {n}"
+            );
+        }
+    }
+}
@@ -1,14 +1,23 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+use crate::errors::Error;
+
 /// The various units of [Weight] that we can display
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WeightUnit {
     Micrograms,
     Milligrams,
     Grams,
     Kilograms,
+    /// The metric tonne, 1000 kilograms
+    Tonnes,
+    /// The international avoirdupois pound, exactly 453.59237 grams
+    Pounds,
+    /// The international avoirdupois ounce, exactly 1/16 of a pound
+    Ounces,
 }
 impl WeightUnit {
     fn best_unit(weight: &Weight) -> Self {
@@ -20,14 +29,25 @@ impl WeightUnit {
         }
     }
 
-    fn unit_ratio(&self) -> f64 {
+    /// The exact number of micrograms in one [WeightUnit], as a
+    /// `(numerator, denominator)` fraction because an ounce is not a whole
+    /// number of micrograms.
+    fn ug_ratio(&self) -> (u128, u128) {
         match self {
-            WeightUnit::Micrograms => 1.0,
-            WeightUnit::Milligrams => 1_000.0,
-            WeightUnit::Grams => 1_000_000.0,
-            WeightUnit::Kilograms => 1_000_000_000.0,
+            WeightUnit::Micrograms => (1, 1),
+            WeightUnit::Milligrams => (1_000, 1),
+            WeightUnit::Grams => (1_000_000, 1),
+            WeightUnit::Kilograms => (1_000_000_000, 1),
+            WeightUnit::Tonnes => (1_000_000_000_000, 1),
+            WeightUnit::Pounds => (453_592_370, 1),
+            WeightUnit::Ounces => (226_796_185, 8),
         }
     }
+
+    fn unit_ratio(&self) -> f64 {
+        let (numerator, denominator) = self.ug_ratio();
+        numerator as f64 / denominator as f64
+    }
 }
 impl Display for WeightUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -36,6 +56,26 @@ impl Display for WeightUnit {
             WeightUnit::Milligrams => write!(f, "mg"),
             WeightUnit::Grams => write!(f, "g"),
             WeightUnit::Kilograms => write!(f, "kg"),
+            WeightUnit::Tonnes => write!(f, "t"),
+            WeightUnit::Pounds => write!(f, "lb"),
+            WeightUnit::Ounces => write!(f, "oz"),
+        }
+    }
+}
+/// The inverse of the [Display] implementation of [WeightUnit]
+impl FromStr for WeightUnit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ug" => Ok(WeightUnit::Micrograms),
+            "mg" => Ok(WeightUnit::Milligrams),
+            "g" => Ok(WeightUnit::Grams),
+            "kg" => Ok(WeightUnit::Kilograms),
+            "t" => Ok(WeightUnit::Tonnes),
+            "lb" => Ok(WeightUnit::Pounds),
+            "oz" => Ok(WeightUnit::Ounces),
+            _ => Err(Error::UnknownWeightUnit(s.to_owned())),
         }
     }
 }
@@ -50,6 +90,9 @@ impl Weight {
     pub const ZERO: Weight = Weight(0);
     pub const MIN: Weight = Weight(80_000_000_000);
     pub const MAX: Weight = Weight(160_000_000_000);
+    /// The maximum number of significant decimals accepted by [Weight::from_str],
+    /// way beyond the microgram precision whatever the [WeightUnit]
+    const MAX_PARSED_DECIMALS: usize = 18;
 
     /// Instantiate a [Weight] from a [f64] in the specified [WeightUnit].
     pub fn from_unit(weight: f64, wu: WeightUnit) -> Self {
@@ -70,19 +113,350 @@ impl Weight {
     pub fn as_ug(&self) -> u64 {
         self.0
     }
+
+    /// Check that the [Weight] is a plausible [Weight] for a [Sheep]
+    /// # Errors
+    /// Returns an [Error::InvalidWeight] if the [Weight] is not between
+    /// [Weight::MIN] and [Weight::MAX].
+    pub fn validate(&self) -> Result<(), Error> {
+        if (Weight::MIN..=Weight::MAX).contains(self) {
+            Ok(())
+        } else {
+            Err(Error::InvalidWeight(*self))
+        }
+    }
+
+    /// Return a [Display]able version of the [Weight] following the [WeightFormat]
+    pub fn display_with(&self, format: WeightFormat) -> WeightDisplay {
+        WeightDisplay {
+            weight: *self,
+            format,
+        }
+    }
+
+    /// Return `true` if the [Weight] expressed in micrograms is a prime number.
+    pub fn is_prime_ug(&self) -> bool {
+        crate::primality::is_prime(self.0)
+    }
+
+    /// Add two [Weight]s, returning [None] on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    /// Add two [Weight]s, saturating at the numeric bound instead of overflowing.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    /// Subtract a [Weight], returning [None] if `rhs` is heavier than `self`.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    /// Subtract a [Weight], saturating at [Weight::ZERO].
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Multiply a [Weight] by a scalar, returning [None] on overflow.
+    pub fn checked_mul(self, rhs: u64) -> Option<Self> {
+        self.0.checked_mul(rhs).map(Self)
+    }
+
+    /// Sum all the [Weight]s of an [Iterator]
+    /// # Errors
+    /// Returns an [Error::WeightOverflow] if the total does not fit in a [Weight].
+    pub fn checked_sum<I: IntoIterator<Item = Weight>>(weights: I) -> Result<Self, Error> {
+        weights
+            .into_iter()
+            .try_fold(Weight::ZERO, Weight::checked_add)
+            .ok_or(Error::WeightOverflow)
+    }
+
+    /// Return the mean of the [Weight]s of an [Iterator], rounded down,
+    /// or [None] if there is no [Weight] at all.
+    ///
+    /// The total is accumulated on 128 bits so it cannot overflow, and the mean
+    /// of [Weight]s always fits in a [Weight].
+    pub fn average<I: IntoIterator<Item = Weight>>(weights: I) -> Option<Self> {
+        let (total, count) = weights
+            .into_iter()
+            .fold((0u128, 0u128), |(total, count), w| {
+                (total + w.0 as u128, count + 1)
+            });
+        (count > 0).then(|| Self((total / count) as u64))
+    }
 }
 
+/// Print the [Weight] in the best metric [WeightUnit] with 3 decimals,
+/// see [WeightFormat] to choose otherwise.
 impl Display for Weight {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let best_unit = WeightUnit::best_unit(self);
-        write!(f, "{:.3}{best_unit}", self.as_unit(best_unit))
+        self.display_with(WeightFormat::default()).fmt(f)
+    }
+}
+
+/// How to print a [Weight]: in which [WeightUnit] and with how many decimals.
+///
+/// The printed value is computed exactly from the micrograms and rounded to the
+/// nearest (half up), without going through a [f64].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightFormat {
+    unit: Option<WeightUnit>,
+    precision: usize,
+}
+impl WeightFormat {
+    /// The default number of decimals
+    pub const DEFAULT_PRECISION: usize = 3;
+    /// The maximum number of decimals, enough to show a microgram in tonnes
+    pub const MAX_PRECISION: usize = 12;
+
+    /// Force the [WeightUnit], instead of the best metric one for each [Weight]
+    pub fn unit(mut self, unit: WeightUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Set the number of decimals, at most [WeightFormat::MAX_PRECISION]
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision.min(Self::MAX_PRECISION);
+        self
+    }
+
+    /// Build a [WeightFormat] from the `unit` and `precision` query parameters of
+    /// a request, or [None] if there is no `unit` parameter.
+    /// # Errors
+    /// Returns an [Error::UnknownWeightUnit] or an [Error::InvalidPrecision]
+    /// if a parameter cannot be parsed.
+    pub fn from_parameters(
+        unit: Option<&str>,
+        precision: Option<&str>,
+    ) -> Result<Option<Self>, Error> {
+        let Some(unit) = unit else {
+            return Ok(None);
+        };
+        let format = Self::default().unit(unit.parse()?);
+        match precision {
+            Some(precision) => precision
+                .parse::<usize>()
+                .ok()
+                .filter(|p| *p <= Self::MAX_PRECISION)
+                .map(|p| Some(format.precision(p)))
+                .ok_or_else(|| Error::InvalidPrecision(precision.to_owned())),
+            None => Ok(Some(format)),
+        }
+    }
+}
+impl Default for WeightFormat {
+    fn default() -> Self {
+        Self {
+            unit: None,
+            precision: Self::DEFAULT_PRECISION,
+        }
+    }
+}
+
+/// A [Weight] printed with a [WeightFormat], see [Weight::display_with]
+#[derive(Debug, Clone, Copy)]
+pub struct WeightDisplay {
+    weight: Weight,
+    format: WeightFormat,
+}
+impl Display for WeightDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = self
+            .format
+            .unit
+            .unwrap_or_else(|| WeightUnit::best_unit(&self.weight));
+        let precision = self.format.precision;
+        let (numerator, denominator) = unit.ug_ratio();
+        // The value in unit, multiplied by 10^precision and rounded.
+        // Cannot overflow: u64::MAX * 8 * 10^12 * 2 < u128::MAX
+        let scale = 10u128.pow(precision as u32);
+        let scaled =
+            (self.weight.0 as u128 * denominator * scale * 2 + numerator) / (numerator * 2);
+        let (integer, fraction) = (scaled / scale, scaled % scale);
+        if precision == 0 {
+            write!(f, "{integer}{unit}")
+        } else {
+            write!(f, "{integer}.{fraction:0precision$}{unit}")
+        }
     }
 }
+/// The inverse of the [Display] implementation of [Weight]: a decimal number
+/// followed by a [WeightUnit], optionally separated by spaces (e.g. `"82.5kg"`,
+/// `"1500 g"` or `"3.2 lb"`).
+///
+/// The number is parsed exactly, without going through a [f64], and rounded
+/// to the nearest microgram (see [Weight::from_str_exact] to refuse rounding).
+impl FromStr for Weight {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, false)
+    }
+}
+impl Weight {
+    /// Parse a [Weight] like [Weight::from_str], but refuse a number that is not
+    /// a whole number of micrograms instead of rounding it (e.g. `"0.5ug"` or `"1oz"`).
+    /// # Errors
+    /// Returns an [Error::WeightParseError] if the string is not a valid [Weight]
+    /// or if it cannot be represented exactly.
+    pub fn from_str_exact(s: &str) -> Result<Self, Error> {
+        Self::parse(s, true)
+    }
+
+    fn parse(s: &str, exact: bool) -> Result<Self, Error> {
+        let invalid = |reason: &str| Error::WeightParseError(s.to_owned(), reason.to_owned());
+        let input = s.trim();
+        let unit_start = input
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(input.len());
+        let (number, unit) = input.split_at(unit_start);
+        let unit = unit.trim_start();
+        if number.is_empty() {
+            return Err(invalid("missing number"));
+        }
+        if unit.is_empty() {
+            return Err(invalid("missing unit"));
+        }
+        let unit: WeightUnit = unit.parse()?;
+
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if fraction.contains('.') || (integer.is_empty() && fraction.is_empty()) {
+            return Err(invalid("malformed number"));
+        }
+        // Trailing zeros do not change the value
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > Self::MAX_PARSED_DECIMALS {
+            return Err(invalid("too many decimals"));
+        }
+        // number = mantissa / scale
+        let scale = 10u128.pow(fraction.len() as u32);
+        let mantissa = format!("{integer}{fraction}")
+            .trim_start_matches('0')
+            .chars()
+            .try_fold(0u128, |m, digit| {
+                m.checked_mul(10)?
+                    .checked_add(digit.to_digit(10).expect("only digits remain") as u128)
+            })
+            .ok_or_else(|| invalid("too heavy"))?;
+        // The weight is mantissa * numerator / (scale * denominator) micrograms,
+        // rounded to the nearest
+        let (numerator, denominator) = unit.ug_ratio();
+        let divisor = scale * denominator;
+        let dividend = mantissa
+            .checked_mul(numerator)
+            .ok_or_else(|| invalid("too heavy"))?;
+        if exact && dividend % divisor != 0 {
+            return Err(invalid("not a whole number of micrograms"));
+        }
+        let ug = dividend
+            .checked_add(divisor / 2)
+            .ok_or_else(|| invalid("too heavy"))?
+            / divisor;
+        u64::try_from(ug)
+            .map(Weight)
+            .map_err(|_| invalid("too heavy"))
+    }
+}
+
+/// # Panics
+/// Panics on overflow, whatever the build profile; use [Weight::checked_add] or
+/// [Weight::saturating_add] to handle it.
 impl std::ops::Add for Weight {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Weight(self.0 + rhs.0)
+        self.checked_add(rhs).expect("Weight addition overflowed")
+    }
+}
+/// # Panics
+/// Panics if `rhs` is heavier than `self`; use [Weight::checked_sub] or
+/// [Weight::saturating_sub] to handle it.
+impl std::ops::Sub for Weight {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("Weight subtraction underflowed")
+    }
+}
+/// # Panics
+/// Panics on overflow; use [Weight::checked_mul] to handle it.
+impl std::ops::Mul<u64> for Weight {
+    type Output = Self;
+
+    fn mul(self, rhs: u64) -> Self::Output {
+        self.checked_mul(rhs)
+            .expect("Weight multiplication overflowed")
+    }
+}
+/// # Panics
+/// Panics if `rhs` is zero.
+impl std::ops::Div<u64> for Weight {
+    type Output = Self;
+
+    fn div(self, rhs: u64) -> Self::Output {
+        Weight(self.0 / rhs)
+    }
+}
+/// # Panics
+/// Panics on overflow; use [Weight::checked_sum] to handle it.
+impl std::iter::Sum for Weight {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Weight::ZERO, |total, w| total + w)
+    }
+}
+/// # Panics
+/// Panics on overflow; use [Weight::checked_sum] to handle it.
+impl<'a> std::iter::Sum<&'a Weight> for Weight {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Serialize a [Weight] as a human-readable string using its [Display]
+/// implementation (e.g. `"82.500kg"`) and deserialize it with its [FromStr]
+/// implementation, to be used with `#[serde(with = "sheep_shed::weight_human")]`.
+///
+/// Deserialization also accepts a number of micrograms, the default representation
+/// of a [Weight]. Note that [Display] rounds to 3 decimals of the displayed unit.
+pub mod weight_human {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    use super::Weight;
+
+    /// A [Weight] in micrograms or in the human-readable format
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum HumanWeight {
+        Micrograms(u64),
+        Human(String),
+    }
+
+    pub fn serialize<S: Serializer>(weight: &Weight, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(weight)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Weight, D::Error> {
+        match HumanWeight::deserialize(deserializer)? {
+            HumanWeight::Micrograms(ug) => Ok(Weight(ug)),
+            HumanWeight::Human(s) => s.parse().map_err(D::Error::custom),
+        }
+    }
+
+    /// Like [deserialize], but refuse a [Weight] that is not a whole number of
+    /// micrograms (see [Weight::from_str_exact]), to be used with
+    /// `#[serde(deserialize_with = "sheep_shed::weight_human::deserialize_exact")]`.
+    pub fn deserialize_exact<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Weight, D::Error> {
+        match HumanWeight::deserialize(deserializer)? {
+            HumanWeight::Micrograms(ug) => Ok(Weight(ug)),
+            HumanWeight::Human(s) => Weight::from_str_exact(&s).map_err(D::Error::custom),
+        }
     }
 }
 
@@ -95,16 +469,271 @@ impl Display for Tattoo {
     }
 }
 
+/// The identifier of a shed, so several sheds can share the same storage
+/// without their [Tattoo]s colliding.
+///
+/// It is made of 1 to [ShedId::MAX_LEN] ASCII letters, digits, `-` or `_`,
+/// except for the sheds of a tenant (see [ShedId::scoped_to]).
+#[derive(Debug, Clone, Hash, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct ShedId(String);
+impl ShedId {
+    /// The maximum length of a [ShedId]
+    pub const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The [ShedId] of this shed among the sheds of `tenant_id`.
+    ///
+    /// It contains a `:`, that cannot be parsed from a [ShedId], so the sheds of a
+    /// tenant can never be reached from a public [ShedId] or from another tenant.
+    pub fn scoped_to(&self, tenant_id: &TenantId) -> ShedId {
+        Self(format!("{tenant_id}:{self}"))
+    }
+}
+impl FromStr for ShedId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if (1..=Self::MAX_LEN).contains(&s.len()) && s.chars().all(is_valid_char) {
+            Ok(Self(s.to_owned()))
+        } else {
+            Err(Error::InvalidShedId(s.to_owned()))
+        }
+    }
+}
+impl TryFrom<String> for ShedId {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+impl From<ShedId> for String {
+    fn from(value: ShedId) -> Self {
+        value.0
+    }
+}
+impl Display for ShedId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The identifier of a tenant owning its own sheds, see [ShedId::scoped_to].
+///
+/// It is made of 1 to [TenantId::MAX_LEN] ASCII letters, digits, `-` or `_`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TenantId(String);
+impl TenantId {
+    /// The maximum length of a [TenantId]
+    pub const MAX_LEN: usize = 128;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl FromStr for TenantId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if (1..=Self::MAX_LEN).contains(&s.len()) && s.chars().all(is_valid_char) {
+            Ok(Self(s.to_owned()))
+        } else {
+            Err(Error::InvalidTenantId(s.to_owned()))
+        }
+    }
+}
+impl Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The sex of a [Sheep]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sex {
+    Ewe,
+    Ram,
+}
+
+/// A [Sheep] of the shed, identified by its [Tattoo].
+///
+/// Only the [Tattoo] and the [Weight] are mandatory, the other fields are
+/// omitted when serialized empty and take their default value when missing,
+/// so a [Sheep] serialized before they existed can still be deserialized.
+///
+/// Deserialization rejects a [Sheep] whose [Weight] is not valid,
+/// see [Sheep::try_new].
+///
+/// The `version` is maintained by the [crate::SheepShed]: it starts at 0 when the
+/// [Sheep] is added and is incremented by each modification, so a writer can
+/// detect that the [Sheep] changed since it read it
+/// (see [crate::SheepShed::kill_sheep_if_unchanged]).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedSheep")]
 pub struct Sheep {
     pub tattoo: Tattoo,
     pub weight: Weight,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub birth_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sex: Option<Sex>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "is_first_version")]
+    pub version: u64,
+}
+
+/// The `version` of a [Sheep] that was never modified is not serialized
+fn is_first_version(version: &u64) -> bool {
+    *version == 0
+}
+
+/// The raw deserialized form of a [Sheep], before validation
+#[derive(Deserialize)]
+struct UncheckedSheep {
+    tattoo: Tattoo,
+    weight: Weight,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    breed: Option<String>,
+    #[serde(default)]
+    birth_date: Option<NaiveDate>,
+    #[serde(default)]
+    sex: Option<Sex>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    version: u64,
+}
+impl TryFrom<UncheckedSheep> for Sheep {
+    type Error = Error;
+
+    fn try_from(value: UncheckedSheep) -> Result<Self, Self::Error> {
+        let sheep = Sheep {
+            tattoo: value.tattoo,
+            weight: value.weight,
+            name: value.name,
+            breed: value.breed,
+            birth_date: value.birth_date,
+            sex: value.sex,
+            tags: value.tags,
+            version: value.version,
+        };
+        sheep.validate()?;
+        Ok(sheep)
+    }
 }
 impl Display for Sheep {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Sheep({}) weighting {}", self.tattoo, self.weight)
     }
 }
+impl Sheep {
+    /// Create a new [Sheep], without any of the optional fields
+    /// # Errors
+    /// Returns an [Error::InvalidWeight] if the [Weight] is not between
+    /// [Weight::MIN] and [Weight::MAX].
+    pub fn try_new(tattoo: Tattoo, weight: Weight) -> Result<Self, Error> {
+        let sheep = Self {
+            tattoo,
+            weight,
+            name: None,
+            breed: None,
+            birth_date: None,
+            sex: None,
+            tags: vec![],
+            version: 0,
+        };
+        sheep.validate()?;
+        Ok(sheep)
+    }
+
+    /// Check that the [Sheep] is valid, as the fields of a [Sheep] are public
+    /// it may have been built without [Sheep::try_new].
+    /// # Errors
+    /// Returns an [Error::InvalidWeight] if the [Weight] is not between
+    /// [Weight::MIN] and [Weight::MAX].
+    pub fn validate(&self) -> Result<(), Error> {
+        self.weight.validate()
+    }
+
+    /// Return a serializable version of the [Sheep] whose [Weight] is printed
+    /// following the [WeightFormat]. With [None], it is serialized exactly like
+    /// the [Sheep] itself.
+    pub fn formatted(&self, format: Option<WeightFormat>) -> FormattedSheep<'_> {
+        FormattedSheep {
+            sheep: self,
+            format,
+        }
+    }
+}
+
+/// A [Sheep] serialized with a [WeightFormat], see [Sheep::formatted]
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedSheep<'a> {
+    sheep: &'a Sheep,
+    format: Option<WeightFormat>,
+}
+impl Serialize for FormattedSheep<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        /// Serialize the field only if it is not empty, like the [Sheep] does
+        fn optional_field<S: SerializeStruct, T: Serialize>(
+            s: &mut S,
+            key: &'static str,
+            value: Option<&T>,
+        ) -> Result<(), S::Error> {
+            match value {
+                Some(value) => s.serialize_field(key, value),
+                None => s.skip_field(key),
+            }
+        }
+
+        let Sheep {
+            tattoo,
+            weight,
+            name,
+            breed,
+            birth_date,
+            sex,
+            tags,
+            version,
+        } = self.sheep;
+        let mut sheep = serializer.serialize_struct("Sheep", 8)?;
+        sheep.serialize_field("tattoo", tattoo)?;
+        match self.format {
+            Some(format) => {
+                sheep.serialize_field("weight", &weight.display_with(format).to_string())?
+            }
+            None => sheep.serialize_field("weight", weight)?,
+        }
+        optional_field(&mut sheep, "name", name.as_ref())?;
+        optional_field(&mut sheep, "breed", breed.as_ref())?;
+        optional_field(&mut sheep, "birth_date", birth_date.as_ref())?;
+        optional_field(&mut sheep, "sex", sex.as_ref())?;
+        optional_field(&mut sheep, "tags", (!tags.is_empty()).then_some(tags))?;
+        optional_field(
+            &mut sheep,
+            "version",
+            (!is_first_version(version)).then_some(version),
+        )?;
+        sheep.end()
+    }
+}
+
 /// Sheeps equality is only driven by their [Tattoo] equality
 impl PartialEq for Sheep {
     fn eq(&self, other: &Self) -> bool {
@@ -125,6 +754,45 @@ impl std::hash::Hash for Sheep {
 mod tests {
     use super::*;
 
+    #[test]
+    fn shed_id_parsing() {
+        for valid in ["a", "farm-1", "North_Farm", &"x".repeat(ShedId::MAX_LEN)] {
+            assert_eq!(valid.parse::<ShedId>().unwrap().as_str(), valid);
+        }
+        for invalid in [
+            "",
+            "farm#1",
+            "farm 1",
+            "ferme-é",
+            &"x".repeat(ShedId::MAX_LEN + 1),
+        ] {
+            assert!(invalid
+                .parse::<ShedId>()
+                .is_err_and(|e| matches!(e, Error::InvalidShedId(_))));
+        }
+        assert_eq!(
+            serde_json::from_str::<ShedId>("\"farm-1\"").unwrap(),
+            "farm-1".parse().unwrap()
+        );
+        assert!(serde_json::from_str::<ShedId>("\"farm#1\"").is_err());
+    }
+
+    #[test]
+    fn shed_id_scoped_to_tenant() {
+        let shed_id: ShedId = "farm".parse().unwrap();
+        let tenant_id: TenantId = "4f1c2a9e-77b0-4c1e-9d3a-5b8e0f6a1c2d".parse().unwrap();
+        let scoped = shed_id.scoped_to(&tenant_id);
+        assert_eq!(scoped.as_str(), "4f1c2a9e-77b0-4c1e-9d3a-5b8e0f6a1c2d:farm");
+        // A tenant shed cannot be named by a public shed id
+        assert!(scoped.as_str().parse::<ShedId>().is_err());
+        assert_ne!(scoped, shed_id.scoped_to(&"other".parse().unwrap()));
+        for invalid in ["", "a:b", &"x".repeat(TenantId::MAX_LEN + 1)] {
+            assert!(invalid
+                .parse::<TenantId>()
+                .is_err_and(|e| matches!(e, Error::InvalidTenantId(_))));
+        }
+    }
+
     #[test]
     fn weight_display() {
         assert_eq!(
@@ -150,6 +818,397 @@ mod tests {
         );
     }
 
+    #[test]
+    fn weight_from_str() {
+        let parse = |s: &str| s.parse::<Weight>().unwrap();
+        assert_eq!(parse("82.5kg"), Weight::from_ug(82_500_000_000));
+        assert_eq!(parse("1500 g"), Weight::from_ug(1_500_000_000));
+        assert_eq!(parse("3.2 lb"), Weight::from_ug(1_451_495_584));
+        assert_eq!(parse("  976ug "), Weight::from_ug(976));
+        assert_eq!(parse(".5mg"), Weight::from_ug(500));
+        assert_eq!(parse("7.mg"), Weight::from_ug(7_000));
+        assert_eq!(parse("0.000000001kg"), Weight::from_ug(1));
+        assert_eq!(
+            parse("1.430983000000000000000kg"),
+            Weight::from_ug(1_430_983_000)
+        );
+        // Precise where a f64 would not be
+        assert_eq!(parse("0.3kg"), Weight::from_ug(300_000_000));
+        assert_eq!(parse("18446744073.709551615kg"), Weight::from_ug(u64::MAX));
+        // Rounded to the nearest microgram
+        assert_eq!(parse("0.4ug"), Weight::from_ug(0));
+        assert_eq!(parse("0.5ug"), Weight::from_ug(1));
+        assert_eq!(parse("0.01lb"), Weight::from_ug(4_535_924));
+    }
+
+    #[test]
+    fn weight_from_str_errors() {
+        let reason = |s: &str| match s.parse::<Weight>() {
+            Err(Error::WeightParseError(input, reason)) => {
+                assert_eq!(input, s);
+                reason
+            }
+            other => panic!("unexpected result for {s:?}: {other:?}"),
+        };
+        assert_eq!(reason(""), "missing number");
+        assert_eq!(reason("kg"), "missing number");
+        assert_eq!(reason("-1kg"), "missing number");
+        assert_eq!(reason("12"), "missing unit");
+        assert_eq!(reason(".kg"), "malformed number");
+        assert_eq!(reason("1.2.3kg"), "malformed number");
+        assert_eq!(reason("0.0000000000000000001kg"), "too many decimals");
+        assert_eq!(reason("18446744073.709551616kg"), "too heavy");
+        assert_eq!(
+            reason("99999999999999999999999999999999999999999kg"),
+            "too heavy"
+        );
+        assert!(matches!(
+            "12 stone".parse::<Weight>(),
+            Err(Error::UnknownWeightUnit(unit)) if unit == "stone"
+        ));
+        assert!(matches!(
+            "1 2kg".parse::<Weight>(),
+            Err(Error::UnknownWeightUnit(unit)) if unit == "2kg"
+        ));
+    }
+
+    #[test]
+    fn weight_display_round_trip() {
+        for ug in [
+            0,
+            976,
+            1_000,
+            432_870,
+            489_440_000,
+            1_431_000_000,
+            82_500_000_000,
+            160_000_000_000,
+        ] {
+            let weight = Weight::from_ug(ug);
+            assert_eq!(weight.to_string().parse::<Weight>().unwrap(), weight);
+        }
+        // Beyond the displayed precision, the Display is stable
+        let weight = Weight::from_unit(1.430983, WeightUnit::Kilograms);
+        let displayed = weight.to_string();
+        assert_eq!(displayed.parse::<Weight>().unwrap().to_string(), displayed);
+    }
+
+    #[test]
+    fn weight_human_serde() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct WeighIn {
+            #[serde(with = "weight_human")]
+            weight: Weight,
+        }
+        let weigh_in: WeighIn = serde_json::from_str(r#"{"weight": "82.5 kg"}"#).unwrap();
+        assert_eq!(weigh_in.weight, Weight::from_ug(82_500_000_000));
+        assert_eq!(
+            serde_json::to_string(&weigh_in).unwrap(),
+            r#"{"weight":"82.500kg"}"#
+        );
+        let weigh_in: WeighIn = serde_json::from_str(r#"{"weight": 976}"#).unwrap();
+        assert_eq!(weigh_in.weight, Weight::from_ug(976));
+        let error = serde_json::from_str::<WeighIn>(r#"{"weight": "heavy"}"#).unwrap_err();
+        assert!(error.to_string().contains("missing number"));
+    }
+
+    #[test]
+    fn weight_units_conversions() {
+        let parse = |s: &str| s.parse::<Weight>().unwrap();
+        assert_eq!(parse("1t"), parse("1000kg"));
+        assert_eq!(parse("1lb"), parse("16oz"));
+        assert_eq!(parse("1lb"), parse("453.59237g"));
+        assert_eq!(parse("1oz"), Weight::from_ug(28_349_523));
+        assert_eq!(parse("8oz"), Weight::from_ug(226_796_185));
+        assert_eq!(parse("0.1t"), Weight::from_ug(100_000_000_000));
+        assert_eq!(
+            Weight::from_unit(2.0, WeightUnit::Pounds),
+            Weight::from_ug(907_184_740)
+        );
+    }
+
+    #[test]
+    fn weight_from_str_exact() {
+        let exact = |s: &str| Weight::from_str_exact(s);
+        assert_eq!(exact("128.01kg").unwrap(), Weight::from_ug(128_010_000_000));
+        assert_eq!(
+            exact("128.010000000 kg").unwrap(),
+            exact("128.01kg").unwrap()
+        );
+        assert_eq!(exact("1.5ug").ok(), None);
+        assert_eq!(exact("0.000000000000001t").ok(), None);
+        assert_eq!(exact("1lb").unwrap(), Weight::from_ug(453_592_370));
+        // 1oz is 28349523.125ug, only a multiple of 8 ounces is exact
+        assert_eq!(exact("1oz").ok(), None);
+        assert_eq!(exact("8oz").unwrap(), Weight::from_ug(226_796_185));
+        assert!(exact("1.5ug")
+            .unwrap_err()
+            .to_string()
+            .contains("not a whole number of micrograms"));
+        // The rounding parse still accepts them
+        assert_eq!("1.5ug".parse::<Weight>().unwrap(), Weight::from_ug(2));
+
+        #[derive(Debug, Deserialize)]
+        struct WeighIn {
+            #[serde(deserialize_with = "weight_human::deserialize_exact")]
+            weight: Weight,
+        }
+        let weigh_in: WeighIn = serde_json::from_str(r#"{"weight": "128.01kg"}"#).unwrap();
+        assert_eq!(weigh_in.weight, Weight::from_ug(128_010_000_000));
+        let weigh_in: WeighIn = serde_json::from_str(r#"{"weight": 976}"#).unwrap();
+        assert_eq!(weigh_in.weight, Weight::from_ug(976));
+        assert!(serde_json::from_str::<WeighIn>(r#"{"weight": "1oz"}"#).is_err());
+    }
+
+    #[test]
+    fn weight_display_with() {
+        let weight = Weight::from_ug(82_500_000_000);
+        let format = WeightFormat::default();
+        assert_eq!(weight.display_with(format).to_string(), weight.to_string());
+        assert_eq!(
+            weight
+                .display_with(format.unit(WeightUnit::Pounds))
+                .to_string(),
+            "181.881lb"
+        );
+        assert_eq!(
+            weight
+                .display_with(format.unit(WeightUnit::Ounces).precision(1))
+                .to_string(),
+            "2910.1oz"
+        );
+        assert_eq!(
+            weight
+                .display_with(format.unit(WeightUnit::Tonnes).precision(12))
+                .to_string(),
+            "0.082500000000t"
+        );
+        assert_eq!(
+            weight
+                .display_with(format.unit(WeightUnit::Grams).precision(0))
+                .to_string(),
+            "82500g"
+        );
+        // Best unit with a custom precision, the precision is capped
+        assert_eq!(
+            weight.display_with(format.precision(1)).to_string(),
+            "82.5kg"
+        );
+        assert_eq!(
+            format.precision(100),
+            format.precision(WeightFormat::MAX_PRECISION)
+        );
+        // Rounded half up, at microgram precision
+        assert_eq!(Weight::from_ug(1_000_500).to_string(), "1.001g");
+        assert_eq!(Weight::from_ug(1_000_499).to_string(), "1.000g");
+        assert_eq!(
+            Weight::from_ug(u64::MAX)
+                .display_with(format.unit(WeightUnit::Micrograms).precision(0))
+                .to_string(),
+            u64::MAX.to_string() + "ug"
+        );
+        // What is displayed can be parsed back exactly
+        let displayed = weight
+            .display_with(format.unit(WeightUnit::Ounces).precision(9))
+            .to_string();
+        assert_eq!(displayed.parse::<Weight>().unwrap(), weight);
+    }
+
+    #[test]
+    fn weight_format_from_parameters() {
+        assert_eq!(
+            WeightFormat::from_parameters(None, Some("2")).unwrap(),
+            None
+        );
+        assert_eq!(
+            WeightFormat::from_parameters(Some("lb"), None).unwrap(),
+            Some(WeightFormat::default().unit(WeightUnit::Pounds))
+        );
+        assert_eq!(
+            WeightFormat::from_parameters(Some("oz"), Some("0")).unwrap(),
+            Some(
+                WeightFormat::default()
+                    .unit(WeightUnit::Ounces)
+                    .precision(0)
+            )
+        );
+        assert!(matches!(
+            WeightFormat::from_parameters(Some("stone"), None),
+            Err(Error::UnknownWeightUnit(_))
+        ));
+        for precision in ["-1", "two", "13"] {
+            assert!(matches!(
+                WeightFormat::from_parameters(Some("kg"), Some(precision)),
+                Err(Error::InvalidPrecision(p)) if p == precision
+            ));
+        }
+    }
+
+    #[test]
+    fn formatted_sheep_serialization() {
+        let sheep = Sheep::try_new(Tattoo(1), Weight::from_ug(82_500_000_000)).unwrap();
+        assert_eq!(
+            serde_json::to_string(&sheep.formatted(None)).unwrap(),
+            serde_json::to_string(&sheep).unwrap()
+        );
+        let format = WeightFormat::default()
+            .unit(WeightUnit::Pounds)
+            .precision(2);
+        assert_eq!(
+            serde_json::to_string(&sheep.formatted(Some(format))).unwrap(),
+            r#"{"tattoo":1,"weight":"181.88lb"}"#
+        );
+        let sheep = Sheep {
+            name: Some("Dolly".to_owned()),
+            tags: vec!["clone".to_owned()],
+            version: 2,
+            ..sheep
+        };
+        assert_eq!(
+            serde_json::to_string(&sheep.formatted(None)).unwrap(),
+            serde_json::to_string(&sheep).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_string(&sheep.formatted(Some(format))).unwrap(),
+            r#"{"tattoo":1,"weight":"181.88lb","name":"Dolly","tags":["clone"],"version":2}"#
+        );
+    }
+
+    #[test]
+    fn sheep_optional_fields_serde() {
+        // A sheep serialized before the optional fields existed
+        let sheep: Sheep =
+            serde_json::from_str(r#"{"tattoo": 1, "weight": 100000000000}"#).unwrap();
+        assert_eq!(sheep.name, None);
+        assert_eq!(sheep.breed, None);
+        assert_eq!(sheep.birth_date, None);
+        assert_eq!(sheep.sex, None);
+        assert!(sheep.tags.is_empty());
+        // Empty optional fields are not serialized
+        assert_eq!(
+            serde_json::to_string(&sheep).unwrap(),
+            r#"{"tattoo":1,"weight":100000000000}"#
+        );
+
+        assert_eq!(sheep.version, 0);
+
+        let json = r#"{"tattoo":1,"weight":100000000000,"name":"Dolly","breed":"Finn Dorset","birth_date":"1996-07-05","sex":"ewe","tags":["clone","famous"],"version":3}"#;
+        let sheep: Sheep = serde_json::from_str(json).unwrap();
+        assert_eq!(sheep.name.as_deref(), Some("Dolly"));
+        assert_eq!(sheep.breed.as_deref(), Some("Finn Dorset"));
+        assert_eq!(sheep.birth_date, NaiveDate::from_ymd_opt(1996, 7, 5));
+        assert_eq!(sheep.sex, Some(Sex::Ewe));
+        assert_eq!(sheep.tags, ["clone", "famous"]);
+        assert_eq!(sheep.version, 3);
+        assert_eq!(serde_json::to_string(&sheep).unwrap(), json);
+
+        for invalid in [
+            r#"{"tattoo":1,"weight":100000000000,"birth_date":"1996-13-05"}"#,
+            r#"{"tattoo":1,"weight":100000000000,"sex":"wether"}"#,
+        ] {
+            assert!(serde_json::from_str::<Sheep>(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn weight_is_prime_ug() {
+        let primes = [2, 3, 5, 7, 97, 7919, 100_000_000_003, 120_000_000_007];
+        let not_primes = [0, 1, 4, 9, 7917, 100_000_000_001, 120_000_000_000];
+        for p in primes {
+            assert!(Weight::from_ug(p).is_prime_ug(), "{p} is prime");
+        }
+        for np in not_primes {
+            assert!(!Weight::from_ug(np).is_prime_ug(), "{np} is not prime");
+        }
+    }
+
+    #[test]
+    fn weight_arithmetic() {
+        let w = |ug| Weight::from_ug(ug);
+        assert_eq!(w(3) + w(4), w(7));
+        assert_eq!(w(7) - w(4), w(3));
+        assert_eq!(w(7) * 3, w(21));
+        assert_eq!(w(7) / 2, w(3));
+        assert_eq!(w(u64::MAX).checked_add(w(1)), None);
+        assert_eq!(w(u64::MAX - 1).checked_add(w(1)), Some(w(u64::MAX)));
+        assert_eq!(w(u64::MAX).saturating_add(w(1)), w(u64::MAX));
+        assert_eq!(w(1).checked_sub(w(2)), None);
+        assert_eq!(w(1).saturating_sub(w(2)), Weight::ZERO);
+        assert_eq!(w(u64::MAX / 2 + 1).checked_mul(2), None);
+        assert_eq!([w(1), w(2), w(3)].into_iter().sum::<Weight>(), w(6));
+        assert_eq!([w(1), w(2), w(3)].iter().sum::<Weight>(), w(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "Weight addition overflowed")]
+    fn weight_add_overflow_panics() {
+        let _ = Weight::from_ug(u64::MAX) + Weight::from_ug(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Weight subtraction underflowed")]
+    fn weight_sub_underflow_panics() {
+        let _ = Weight::from_ug(1) - Weight::from_ug(2);
+    }
+
+    #[test]
+    fn weight_checked_sum_and_average() {
+        let w = |ug| Weight::from_ug(ug);
+        assert_eq!(Weight::checked_sum([]).unwrap(), Weight::ZERO);
+        assert_eq!(Weight::checked_sum([w(1), w(2), w(3)]).unwrap(), w(6));
+        assert!(matches!(
+            Weight::checked_sum([w(u64::MAX), w(1)]),
+            Err(Error::WeightOverflow)
+        ));
+        assert_eq!(Weight::average([]), None);
+        assert_eq!(Weight::average([w(1), w(2), w(4)]), Some(w(2)));
+        // The mean is right even when the total overflows a u64
+        assert_eq!(
+            Weight::average([w(u64::MAX), w(u64::MAX), w(u64::MAX - 2)]),
+            Some(w(u64::MAX - 1))
+        );
+    }
+
+    #[test]
+    fn weight_unit_from_str() {
+        for wu in [
+            WeightUnit::Micrograms,
+            WeightUnit::Milligrams,
+            WeightUnit::Grams,
+            WeightUnit::Kilograms,
+            WeightUnit::Tonnes,
+            WeightUnit::Pounds,
+            WeightUnit::Ounces,
+        ] {
+            let parsed: WeightUnit = wu.to_string().parse().unwrap();
+            assert_eq!(parsed.to_string(), wu.to_string());
+        }
+        assert!("stone"
+            .parse::<WeightUnit>()
+            .is_err_and(|e| matches!(e, Error::UnknownWeightUnit(_))));
+    }
+
+    #[test]
+    fn sheep_weight_validation() {
+        for weight in [Weight::MIN, Weight::MAX, Weight::from_ug(100_000_000_000)] {
+            assert_eq!(Sheep::try_new(Tattoo(1), weight).unwrap().weight, weight);
+        }
+        for weight in [
+            Weight::ZERO,
+            Weight::from_ug(Weight::MIN.as_ug() - 1),
+            Weight::from_ug(Weight::MAX.as_ug() + 1),
+        ] {
+            assert!(matches!(
+                Sheep::try_new(Tattoo(1), weight),
+                Err(Error::InvalidWeight(w)) if w == weight
+            ));
+        }
+        let sheep: Sheep = serde_json::from_str(r#"{"tattoo": 1, "weight": 80000000000}"#).unwrap();
+        assert_eq!(sheep.weight, Weight::MIN);
+        let error = serde_json::from_str::<Sheep>(r#"{"tattoo": 1, "weight": 0}"#).unwrap_err();
+        assert!(error.to_string().contains("Invalid weight"));
+    }
+
     #[test]
     fn sheeps_equal_equiv_tattoos_equal() {
         let t1 = Tattoo(1);
@@ -157,22 +1216,10 @@ mod tests {
         let w1 = Weight::from_unit(100.0, WeightUnit::Kilograms);
         let w2 = Weight::from_unit(150.0, WeightUnit::Kilograms);
 
-        let sheep1 = Sheep {
-            tattoo: t1.clone(),
-            weight: w1,
-        };
-        let sheep2 = Sheep {
-            tattoo: t2.clone(),
-            weight: w1,
-        };
-        let sheep3 = Sheep {
-            tattoo: t1,
-            weight: w2,
-        };
-        let sheep4 = Sheep {
-            tattoo: t2,
-            weight: w2,
-        };
+        let sheep1 = Sheep::try_new(t1.clone(), w1).unwrap();
+        let sheep2 = Sheep::try_new(t2.clone(), w1).unwrap();
+        let sheep3 = Sheep::try_new(t1, w2).unwrap();
+        let sheep4 = Sheep::try_new(t2, w2).unwrap();
         assert_eq!(sheep1, sheep3);
         assert_eq!(sheep2, sheep4);
         assert_ne!(sheep1, sheep2);
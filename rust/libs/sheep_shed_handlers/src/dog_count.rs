@@ -0,0 +1,21 @@
+use lambda_apigw_utils::prelude::*;
+use sheep_shed::{errors::Error, AsyncSheepShed, ShedId};
+
+use crate::shed_id_parameter;
+
+/// Count the sheeps of the shed
+pub async fn bark_answer<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let shed_id = shed_id_parameter(&req.parameters)?;
+
+    log::info!("create a shed instance");
+    let sheep_shed = new_shed(shed_id)?;
+
+    log::info!("counting sheeps...");
+    let count = sheep_shed.sheep_count().await?;
+
+    log::info!("success - count={count}");
+    simple_response!(200, json!({"count": count}))
+}
@@ -0,0 +1,324 @@
+//! The handlers of the sheep lambdas, shared by the public lambdas and their
+//! tenant counterparts.
+//!
+//! Each handler is generic over the [AsyncSheepShed] and receives a function
+//! creating the shed of a [ShedId]: the lambdas only differ by the shed they
+//! create, e.g. a `DynamoDBSheepShed` for the public ones and a `TenantSheepShed`
+//! for the tenant ones.
+use std::collections::HashMap;
+
+use lambda_apigw_utils::prelude::*;
+use sheep_shed::{ShedId, Tattoo, WeightFormat};
+
+mod dog_count;
+mod sheep_by_tattoo;
+mod sheep_history;
+mod sheep_random;
+mod sheep_transfer;
+mod sheep_weight;
+mod sheeps_batch;
+mod sheeps_page;
+mod wolf_ocd;
+
+pub use dog_count::bark_answer;
+pub use sheep_by_tattoo::retrieve_sheep;
+pub use sheep_history::retrieve_history;
+pub use sheep_random::insert_sheep;
+pub use sheep_transfer::transfer_sheep;
+pub use sheep_weight::weigh_sheep;
+pub use sheeps_batch::insert_sheeps;
+pub use sheeps_page::list_sheeps;
+pub use wolf_ocd::wolf_ocd;
+
+/// Parse the `ShedId` path parameter
+fn shed_id_parameter(parameters: &HashMap<&str, &str>) -> Result<ShedId, SimpleError> {
+    let shed_id: ShedId = parameters
+        .get("ShedId")
+        .expect("API Gateway ensures it's here")
+        .parse()?;
+
+    log::info!("shed_id={shed_id}");
+    Ok(shed_id)
+}
+
+/// Parse the `Tattoo` path parameter
+fn tattoo_parameter(parameters: &HashMap<&str, &str>) -> Result<Tattoo, SimpleError> {
+    let tattoo_parameter = *parameters
+        .get("Tattoo")
+        .expect("API Gateway ensures it's here");
+    let tattoo = Tattoo(tattoo_parameter.parse().map_err(|e| {
+        SimpleError::InvalidInput(format!(
+            "Tattoo parameter {tattoo_parameter} could not be parsed: {e}"
+        ))
+    })?);
+
+    log::info!("tattoo={tattoo:?}");
+    Ok(tattoo)
+}
+
+/// Parse the optional `unit` and `precision` query parameters
+fn weight_format_parameters(
+    parameters: &HashMap<&str, &str>,
+) -> Result<Option<WeightFormat>, SimpleError> {
+    let weight_format = WeightFormat::from_parameters(
+        parameters.get("unit").copied(),
+        parameters.get("precision").copied(),
+    )?;
+
+    log::info!("weight_format={weight_format:?}");
+    Ok(weight_format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::Client;
+    use dynamodb_sheep_shed::{DynamoDBSheepShedBuilder, SheepTable};
+    use sheep_shed::{errors::Error, AsyncSheepShed, MemorySheepShed, Sheep, Weight};
+    use sheeps_batch::MAX_BATCH_SIZE;
+
+    fn request<'a>(parameters: &[(&'a str, &'a str)], body: &'a str) -> SimpleRequest<'a> {
+        SimpleRequest {
+            cognito_values: None,
+            parameters: parameters.iter().copied().collect(),
+            body,
+        }
+    }
+
+    async fn shed_with_sheeps(tattoos: &[u64]) -> MemorySheepShed {
+        let mut sheep_shed = MemorySheepShed::default();
+        for &tattoo in tattoos {
+            sheep_shed
+                .add_sheep(
+                    Sheep::try_new(Tattoo(tattoo), Weight::from_ug(100_000_000_000)).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+        sheep_shed
+    }
+
+    #[tokio::test]
+    async fn handlers_use_the_shed_of_the_factory() {
+        let sheep_shed = shed_with_sheeps(&[1, 2, 3]).await;
+        let response = bark_answer(request(&[("ShedId", "farm")], ""), |shed_id| {
+            assert_eq!(shed_id, "farm".parse().unwrap());
+            Ok(sheep_shed.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.code, 200);
+        assert_eq!(response.body, Some(json!({"count": 3})));
+    }
+
+    #[tokio::test]
+    async fn factory_errors_are_returned() {
+        let result = bark_answer(request(&[("ShedId", "farm")], ""), |_| {
+            Err::<MemorySheepShed, _>(Error::InvalidConfiguration("no table".to_owned()))
+        })
+        .await;
+        assert!(matches!(result, Err(SimpleError::ServerError(_))));
+    }
+
+    #[tokio::test]
+    async fn unknown_sheep_is_not_found() {
+        let sheep_shed = shed_with_sheeps(&[1]).await;
+        let result = retrieve_sheep(request(&[("ShedId", "farm"), ("Tattoo", "2")], ""), |_| {
+            Ok(sheep_shed.clone())
+        })
+        .await;
+        assert!(matches!(result, Err(SimpleError::Custom { code: 404, .. })));
+    }
+
+    #[tokio::test]
+    async fn invalid_tattoo_is_refused() {
+        let result = retrieve_sheep(
+            request(&[("ShedId", "farm"), ("Tattoo", "dolly")], ""),
+            |_| Ok(MemorySheepShed::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(SimpleError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn transfer_creates_both_sheds() {
+        let sheep_shed = shed_with_sheeps(&[1]).await;
+        let mut created = Vec::new();
+        let response = transfer_sheep(
+            request(
+                &[("ShedId", "farm"), ("Tattoo", "1")],
+                r#"{"to": "north-farm"}"#,
+            ),
+            |shed_id| {
+                created.push(shed_id.clone());
+                Ok(if shed_id == "farm".parse().unwrap() {
+                    sheep_shed.clone()
+                } else {
+                    MemorySheepShed::default()
+                })
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.code, 200);
+        assert_eq!(response.body.unwrap()["shed_id"], json!("north-farm"));
+        assert_eq!(
+            created,
+            vec!["farm".parse().unwrap(), "north-farm".parse().unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn weigh_in_takes_a_human_weight() {
+        let sheep_shed = shed_with_sheeps(&[1]).await;
+        let parameters = [("ShedId", "farm"), ("Tattoo", "1"), ("unit", "g")];
+        let response = weigh_sheep(request(&parameters, r#"{"weight": "120 kg"}"#), |_| {
+            Ok(sheep_shed.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.code, 200);
+        assert_eq!(
+            response.body.unwrap()["current"]["weight"],
+            json!("120000.000g")
+        );
+        for body in [r#"{"weight": 120.5, "unit": "kg"}"#, r#"{"weight": 120.5}"#] {
+            let result = weigh_sheep(request(&parameters, body), |_| Ok(sheep_shed.clone())).await;
+            assert!(matches!(result, Err(SimpleError::InvalidBody)), "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn inexact_weigh_in_is_refused() {
+        let sheep_shed = shed_with_sheeps(&[1]).await;
+        let parameters = [("ShedId", "farm"), ("Tattoo", "1")];
+        for body in [
+            r#"{"weight": "0.128010000000001t"}"#,
+            r#"{"weight": "128.0100000001kg"}"#,
+            r#"{"weight": "4500oz"}"#,
+        ] {
+            let result = weigh_sheep(request(&parameters, body), |_| Ok(sheep_shed.clone())).await;
+            assert!(matches!(result, Err(SimpleError::InvalidBody)), "{body}");
+        }
+    }
+
+    /// A [Client] of the local DynamoDB used by the tests of `dynamodb_sheep_shed`
+    fn dynamodb_local_client() -> Client {
+        let config = aws_sdk_dynamodb::Config::builder()
+            .endpoint_url("http://localhost:8000")
+            .behavior_version_latest()
+            .credentials_provider(aws_sdk_dynamodb::config::Credentials::new(
+                "fakeMyKeyId",
+                "fakeSecretAccessKey",
+                None,
+                None,
+                "Static",
+            ))
+            .region(Some(aws_sdk_dynamodb::config::Region::from_static(
+                "eu-west-1",
+            )))
+            .build();
+        Client::from_conf(config)
+    }
+
+    #[tokio::test]
+    async fn decimal_weigh_in_is_stored_exactly() {
+        let client = dynamodb_local_client();
+        let table_name = "handlers-weigh-in";
+        SheepTable::new(client.clone(), table_name)
+            .ensure_table()
+            .await
+            .unwrap();
+        let new_shed = |shed_id| {
+            DynamoDBSheepShedBuilder::new()
+                .with_client(client.clone())
+                .with_table_name(table_name)
+                .with_shed_id(shed_id)
+                .with_consistent_read(true)
+                .build()
+        };
+        let mut sheep_shed = new_shed("farm".parse().unwrap()).unwrap();
+        let _ = sheep_shed.kill_sheep(&Tattoo(1)).await;
+        sheep_shed
+            .add_sheep(Sheep::try_new(Tattoo(1), Weight::MIN).unwrap())
+            .await
+            .unwrap();
+
+        let parameters = [("ShedId", "farm"), ("Tattoo", "1")];
+        for (body, ug) in [
+            (r#"{"weight": "128.01kg"}"#, 128_010_000_000),
+            (r#"{"weight": "128010.000001 g"}"#, 128_010_000_001),
+            (r#"{"weight": "0.1t"}"#, 100_000_000_000),
+            (r#"{"weight": "250lb"}"#, 113_398_092_500),
+            (r#"{"weight": 99999999999}"#, 99_999_999_999),
+        ] {
+            weigh_sheep(request(&parameters, body), new_shed)
+                .await
+                .unwrap();
+            let sheep = sheep_shed.get_sheep(&Tattoo(1)).await.unwrap().unwrap();
+            assert_eq!(sheep.weight, Weight::from_ug(ug), "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_reports_invalid_sheeps_one_by_one() {
+        let sheep_shed = shed_with_sheeps(&[1]).await;
+        let body = json!([
+            {"tattoo": 1, "weight": 100_000_000_000u64},
+            {"tattoo": 2, "weight": 100_000_000_000u64},
+            {"tattoo": 3, "weight": "heavy"},
+            {"weight": 100_000_000_000u64},
+            "dolly",
+            {"tattoo": 4, "weight": 1},
+        ])
+        .to_string();
+        let response = insert_sheeps(request(&[("ShedId", "farm")], &body), |_| {
+            Ok(sheep_shed.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.code, 207);
+        let results = response.body.unwrap()["results"].clone();
+        let statuses = results
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| (result.get("tattoo").cloned(), result["status"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                (Some(json!(1)), json!("duplicate")),
+                (Some(json!(2)), json!("inserted")),
+                (Some(json!(3)), json!("failed")),
+                (None, json!("failed")),
+                (None, json!("failed")),
+                (Some(json!(4)), json!("failed")),
+            ]
+        );
+        assert!(results[2]["reason"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid sheep"));
+    }
+
+    #[tokio::test]
+    async fn batch_size_is_limited() {
+        let sheeps = (0..=MAX_BATCH_SIZE as u64)
+            .map(|tattoo| json!({"tattoo": tattoo, "weight": 100_000_000_000u64}))
+            .collect::<Vec<_>>();
+        let body = json!(sheeps).to_string();
+        let result = insert_sheeps(request(&[("ShedId", "farm")], &body), |_| {
+            Ok(MemorySheepShed::default())
+        })
+        .await;
+        assert!(matches!(result, Err(SimpleError::Custom { code: 413, .. })));
+        let body = json!(sheeps[1..]).to_string();
+        let response = insert_sheeps(request(&[("ShedId", "farm")], &body), |_| {
+            Ok(MemorySheepShed::default())
+        })
+        .await
+        .unwrap();
+        assert_eq!(response.code, 207);
+    }
+}
@@ -0,0 +1,30 @@
+use lambda_apigw_utils::prelude::*;
+use sheep_shed::{errors::Error, AsyncSheepShed, ShedId};
+
+use crate::{shed_id_parameter, tattoo_parameter, weight_format_parameters};
+
+/// Retrieve the sheep with the `Tattoo` of the path
+pub async fn retrieve_sheep<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let parameters = req.parameters;
+    let shed_id = shed_id_parameter(&parameters)?;
+    let tattoo = tattoo_parameter(&parameters)?;
+    let weight_format = weight_format_parameters(&parameters)?;
+
+    log::info!("create a shed instance");
+    let sheep_shed = new_shed(shed_id)?;
+
+    log::info!("looking for the sheep...");
+    match sheep_shed.get_sheep(&tattoo).await? {
+        Some(sheep) => {
+            log::info!("success - {sheep}");
+            simple_response!(200, json!(sheep.formatted(weight_format)))
+        }
+        None => {
+            log::info!("no sheep with tattoo {tattoo}");
+            Err(Error::SheepNotPresent(tattoo).into())
+        }
+    }
+}
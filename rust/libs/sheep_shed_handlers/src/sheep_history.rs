@@ -0,0 +1,27 @@
+use lambda_apigw_utils::prelude::*;
+use sheep_shed::{errors::Error, AsyncSheepShed, ShedId};
+
+use crate::{shed_id_parameter, tattoo_parameter, weight_format_parameters};
+
+/// Retrieve the weight history of the sheep with the `Tattoo` of the path
+pub async fn retrieve_history<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let parameters = req.parameters;
+    let shed_id = shed_id_parameter(&parameters)?;
+    let tattoo = tattoo_parameter(&parameters)?;
+    let weight_format = weight_format_parameters(&parameters)?;
+
+    log::info!("create a shed instance");
+    let sheep_shed = new_shed(shed_id)?;
+
+    log::info!("retrieving the weight history...");
+    let history = sheep_shed.weight_history(&tattoo).await?;
+    log::info!("success - {} weigh-ins", history.len());
+    let history = history
+        .iter()
+        .map(|weigh_in| weigh_in.formatted(weight_format))
+        .collect::<Vec<_>>();
+    simple_response!(200, json!({"tattoo": tattoo, "history": history}))
+}
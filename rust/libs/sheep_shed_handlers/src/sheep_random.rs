@@ -0,0 +1,90 @@
+use chrono::NaiveDate;
+use lambda_apigw_utils::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Deserializer};
+use sheep_shed::{errors::Error, weight_human, AsyncSheepShed, Sex, ShedId, Sheep, Weight};
+
+use crate::{shed_id_parameter, tattoo_parameter, weight_format_parameters};
+
+/// The optional body of the request, every field is optional and the [Weight]
+/// is random if not given, e.g. `{"weight": "82.5kg", "name": "Dolly", "tags": ["clone"]}`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewSheep {
+    #[serde(default, deserialize_with = "deserialize_weight")]
+    weight: Option<Weight>,
+    name: Option<String>,
+    breed: Option<String>,
+    birth_date: Option<NaiveDate>,
+    sex: Option<Sex>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Accept the [Weight] in the human-readable format or in micrograms
+fn deserialize_weight<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Weight>, D::Error> {
+    weight_human::deserialize(deserializer).map(Some)
+}
+
+/// Create a random weight for a Sheep between 80 and 160 kg
+async fn generate_random_weight() -> Weight {
+    let min = Weight::MIN.as_ug();
+    let max = Weight::MAX.as_ug();
+    Weight::from_ug(rand::thread_rng().gen_range(min..max))
+}
+
+/// Insert a sheep with the `Tattoo` of the path, described by the optional body
+pub async fn insert_sheep<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let parameters = req.parameters;
+    let shed_id = shed_id_parameter(&parameters)?;
+    let tattoo = tattoo_parameter(&parameters)?;
+    let weight_format = weight_format_parameters(&parameters)?;
+
+    let new_sheep: NewSheep = if req.body.trim().is_empty() {
+        NewSheep::default()
+    } else {
+        serde_json::from_str(req.body).map_err(|e| {
+            log::error!("body could not be parsed: {e}");
+            SimpleError::InvalidBody
+        })?
+    };
+
+    log::info!("new_sheep={new_sheep:?}");
+
+    let handle = tokio::runtime::Handle::current();
+
+    log::info!("spawning sheep generation...");
+    // Random number generation in a separate task
+    let new_sheep = handle.spawn(async move {
+        let weight = match new_sheep.weight {
+            Some(weight) => weight,
+            None => generate_random_weight().await,
+        };
+        Sheep::try_new(tattoo, weight).map(|sheep| Sheep {
+            name: new_sheep.name,
+            breed: new_sheep.breed,
+            birth_date: new_sheep.birth_date,
+            sex: new_sheep.sex,
+            tags: new_sheep.tags,
+            ..sheep
+        })
+    });
+
+    log::info!("create a shed instance");
+    let mut sheep_shed = new_shed(shed_id)?;
+
+    log::info!("waiting sheep generation...");
+    let new_sheep = new_sheep.await.unwrap()?;
+    let response = json!(new_sheep.formatted(weight_format));
+
+    log::info!("inserting sheep");
+    sheep_shed.add_sheep(new_sheep).await?;
+
+    log::info!("success");
+    simple_response!(201, response)
+}
@@ -0,0 +1,49 @@
+use lambda_apigw_utils::prelude::*;
+use serde::Deserialize;
+use sheep_shed::{errors::Error, AsyncSheepShed, ShedId};
+
+use crate::{shed_id_parameter, tattoo_parameter, weight_format_parameters};
+
+/// The expected body of the request, e.g. `{"to": "north-farm"}`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Transfer {
+    to: ShedId,
+}
+
+/// Transfer the sheep with the `Tattoo` of the path to the shed of the body,
+/// `new_shed` is called for both sheds
+pub async fn transfer_sheep<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    mut new_shed: impl FnMut(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let parameters = req.parameters;
+    let shed_id = shed_id_parameter(&parameters)?;
+    let tattoo = tattoo_parameter(&parameters)?;
+    let weight_format = weight_format_parameters(&parameters)?;
+
+    let transfer: Transfer = serde_json::from_str(req.body).map_err(|e| {
+        log::error!("body could not be parsed: {e}");
+        SimpleError::InvalidBody
+    })?;
+
+    log::info!("transfer={transfer:?}");
+
+    log::info!("create the shed instances");
+    let mut from_sheep_shed = new_shed(shed_id)?;
+    let mut to_sheep_shed = new_shed(transfer.to.clone())?;
+
+    log::info!("transferring sheep...");
+    let sheep = from_sheep_shed
+        .transfer_sheep(&mut to_sheep_shed, &tattoo)
+        .await?;
+
+    log::info!("success - {sheep} is in shed {}", transfer.to);
+    simple_response!(
+        200,
+        json!({
+            "shed_id": transfer.to,
+            "sheep": sheep.formatted(weight_format)
+        })
+    )
+}
@@ -0,0 +1,56 @@
+use lambda_apigw_utils::prelude::*;
+use serde::Deserialize;
+use sheep_shed::{errors::Error, weight_human, AsyncSheepShed, ShedId, Sheep, Weight};
+
+use crate::{shed_id_parameter, tattoo_parameter, weight_format_parameters};
+
+/// The expected body of the request, the [Weight] in the human-readable format
+/// or in micrograms, e.g. `{"weight": "128.01kg"}`.
+///
+/// A [Weight] that is not a whole number of micrograms (e.g. `"1oz"`) is refused
+/// rather than rounded.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WeighIn {
+    #[serde(deserialize_with = "weight_human::deserialize_exact")]
+    weight: Weight,
+}
+
+/// Weigh the sheep with the `Tattoo` of the path, the new weight is in the body
+pub async fn weigh_sheep<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let parameters = req.parameters;
+    let shed_id = shed_id_parameter(&parameters)?;
+    let tattoo = tattoo_parameter(&parameters)?;
+    let weight_format = weight_format_parameters(&parameters)?;
+
+    let weigh_in: WeighIn = serde_json::from_str(req.body).map_err(|e| {
+        log::error!("body could not be parsed: {e}");
+        SimpleError::InvalidBody
+    })?;
+    let weight = weigh_in.weight;
+
+    log::info!("weight={weight}");
+
+    log::info!("create a shed instance");
+    let mut sheep_shed = new_shed(shed_id)?;
+
+    log::info!("weighing sheep...");
+    let previous = sheep_shed.update_weight(&tattoo, weight).await?;
+    let current = Sheep {
+        weight,
+        version: previous.version + 1,
+        ..previous.clone()
+    };
+
+    log::info!("success - {previous} is now {current}");
+    simple_response!(
+        200,
+        json!({
+            "previous": previous.formatted(weight_format),
+            "current": current.formatted(weight_format)
+        })
+    )
+}
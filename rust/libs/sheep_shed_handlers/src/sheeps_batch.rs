@@ -0,0 +1,98 @@
+use lambda_apigw_utils::prelude::*;
+use serde::Serialize;
+use sheep_shed::{errors::Error, AddSheepOutcome, AsyncSheepShed, ShedId, Sheep, Tattoo};
+
+use crate::shed_id_parameter;
+
+/// The biggest batch a caller can send: the insertion must end well before the
+/// 29 seconds timeout of API Gateway, even when DynamoDB throttles it
+pub(crate) const MAX_BATCH_SIZE: usize = 200;
+
+/// The result reported for each [Sheep] of the batch, in the same order as the batch,
+/// e.g. `{"tattoo": 1, "status": "inserted"}`
+///
+/// The [Tattoo] is absent if the item of the batch does not even have a valid one.
+#[derive(Debug, Serialize)]
+struct SheepResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tattoo: Option<Tattoo>,
+    #[serde(flatten)]
+    outcome: AddSheepOutcome,
+}
+
+/// Insert the sheeps of the body, reporting the outcome of each of them.
+///
+/// Each item of the body is parsed on its own: an invalid one is reported as
+/// failed without preventing the others to be inserted.
+pub async fn insert_sheeps<S: AsyncSheepShed + Send>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let shed_id = shed_id_parameter(&req.parameters)?;
+
+    let items: Vec<serde_json::Value> = serde_json::from_str(req.body).map_err(|e| {
+        log::error!("body could not be parsed: {e}");
+        SimpleError::InvalidBody
+    })?;
+
+    log::info!("items.len()={}", items.len());
+
+    if items.len() > MAX_BATCH_SIZE {
+        return Err(SimpleError::Custom {
+            code: 413,
+            message: format!(
+                "A batch holds at most {MAX_BATCH_SIZE} sheeps, not {}",
+                items.len()
+            ),
+        });
+    }
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut sheeps = vec![];
+    for item in items {
+        let tattoo = item
+            .get("tattoo")
+            .and_then(|tattoo| serde_json::from_value(tattoo.clone()).ok());
+        match serde_json::from_value::<Sheep>(item) {
+            Ok(sheep) => {
+                // The outcome is known once the sheep is inserted
+                results.push(None);
+                sheeps.push(sheep);
+            }
+            Err(e) => {
+                log::info!("invalid sheep {tattoo:?}: {e}");
+                results.push(Some(SheepResult {
+                    tattoo,
+                    outcome: AddSheepOutcome::Failed {
+                        reason: format!("Invalid sheep: {e}"),
+                    },
+                }));
+            }
+        }
+    }
+
+    log::info!("sheeps.len()={}", sheeps.len());
+
+    log::info!("create a shed instance");
+    let mut sheep_shed = new_shed(shed_id)?;
+
+    log::info!("inserting sheeps");
+    let mut outcomes = sheep_shed.add_sheep_batch(sheeps).await?.into_iter();
+    let results = results
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|| {
+                let (tattoo, outcome) = outcomes
+                    .next()
+                    .expect("there is an outcome for each sheep of the batch");
+                SheepResult {
+                    tattoo: Some(tattoo),
+                    outcome,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    log::info!("success");
+    simple_response!(207, json!({"results": results}))
+}
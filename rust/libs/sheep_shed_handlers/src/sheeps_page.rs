@@ -0,0 +1,54 @@
+use lambda_apigw_utils::prelude::*;
+use sheep_shed::{errors::Error, AsyncSheepShed, Cursor, ShedId};
+
+use crate::{shed_id_parameter, weight_format_parameters};
+
+/// The page size used when the `limit` query parameter is absent
+const DEFAULT_LIMIT: usize = 100;
+/// The biggest page size a caller can ask for
+const MAX_LIMIT: usize = 1000;
+
+/// List a page of the sheeps of the shed, starting after the optional `cursor`
+pub async fn list_sheeps<S: AsyncSheepShed>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let parameters = req.parameters;
+    let shed_id = shed_id_parameter(&parameters)?;
+
+    let limit = match parameters.get("limit") {
+        Some(limit_parameter) => limit_parameter
+            .parse::<usize>()
+            .ok()
+            .filter(|limit| (1..=MAX_LIMIT).contains(limit))
+            .ok_or_else(|| {
+                SimpleError::InvalidInput(format!(
+                    "limit parameter {limit_parameter} must be an integer between 1 and {MAX_LIMIT}"
+                ))
+            })?,
+        None => DEFAULT_LIMIT,
+    };
+    let cursor = parameters
+        .get("cursor")
+        .map(|c| c.parse::<Cursor>().expect("infallible"));
+    let weight_format = weight_format_parameters(&parameters)?;
+
+    log::info!("limit={limit} cursor={cursor:?}");
+
+    log::info!("create a shed instance");
+    let sheep_shed = new_shed(shed_id)?;
+
+    log::info!("listing sheeps...");
+    let page = sheep_shed.list_page(cursor.as_ref(), limit).await?;
+
+    log::info!("success - {} sheeps", page.sheeps.len());
+    let sheeps = page
+        .sheeps
+        .iter()
+        .map(|sheep| sheep.formatted(weight_format))
+        .collect::<Vec<_>>();
+    simple_response!(
+        200,
+        json!({"sheeps": sheeps, "next_cursor": page.next_cursor})
+    )
+}
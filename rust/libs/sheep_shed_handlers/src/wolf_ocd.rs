@@ -0,0 +1,39 @@
+use lambda_apigw_utils::prelude::*;
+use sheep_shed::{errors::Error, hunt, AsyncSheepShed, ShedId, WeightCriteria};
+
+use crate::shed_id_parameter;
+
+/// How many times the wolf looks for a sheep when other wolves keep getting to
+/// the one it picked first
+const WOLF_MAX_ATTEMPTS: u32 = 3;
+
+/// This wolf suffer from Obsessive-Compulsive disorder: it is hungry, but it cannot kill just any sheep !!
+///
+/// It is very important for the wolf that the weight of the sheep expressed in micro-grams is a
+/// prime number!!! And of course, the bigest possible.
+///
+/// Other wolves may be hunting at the same time: the wolf only eats the sheep if it did not
+/// change since it was picked, otherwise it picks again, up to [WOLF_MAX_ATTEMPTS] times
+/// (see [hunt]).
+pub async fn wolf_ocd<S: AsyncSheepShed + Sync>(
+    req: SimpleRequest<'_>,
+    new_shed: impl FnOnce(ShedId) -> Result<S, Error>,
+) -> SimpleResult {
+    let shed_id = shed_id_parameter(&req.parameters)?;
+
+    log::info!("create a shed instance");
+    let mut sheep_shed = new_shed(shed_id)?;
+
+    // The shed knows which sheeps have a prime weight, it is asked first
+    match hunt(&mut sheep_shed, WeightCriteria::PrimeUg, WOLF_MAX_ATTEMPTS).await? {
+        Some(sheep) => {
+            log::info!("wolf ate {sheep}");
+            simple_response!(204)
+        }
+        None => {
+            // Do nothing and return 404
+            log::info!("it seems the wolf will continue to starve...");
+            simple_response!(404, json!({"message": "No fitting sheep"}))
+        }
+    }
+}
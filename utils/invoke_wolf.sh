@@ -3,10 +3,11 @@
 usage () {
     echo "Usage: $0 [<OPTIONS>] <API_URL>"
     echo ""
-    echo "Repeatedly call DELETE <API_URL>/wolf"
+    echo "Repeatedly call DELETE <API_URL>/sheds/<shed_id>/wolf"
     echo ""
     echo -e "-p|--parallel <task_count>\tThe number of concurrent task to use. (Default: 100)"
     echo -e "-c|--call-count <count>\tThe number of call to make. (Default: 1000)"
+    echo -e "--shed-id <shed_id>\tThe id of the shed. (Default: default)"
     echo ""
     echo "OPTIONS:"
     echo -e "-h|--help\t\t\tShow this help"
@@ -14,6 +15,7 @@ usage () {
 
 PARALLEL_TASKS=100
 COUNT=1000
+SHED_ID=default
 
 POSITIONAL=()
 while [[ $# -gt 0 ]]
@@ -34,6 +36,11 @@ do
             shift # past argument
             shift # past value
         ;;
+        --shed-id)
+            SHED_ID="$2"
+            shift # past argument
+            shift # past value
+        ;;
         *)    # unknown option
             POSITIONAL+=("$1") # save it in an array for later
             shift # past argument
@@ -56,6 +63,6 @@ timestamp_nano() {
 }
 
 start_ts=$(timestamp_nano)
-seq 1 $COUNT | xargs -Iunused -P$PARALLEL_TASKS curl -s --retry 5 --retry-connrefused -XDELETE "$API_URL/wolf" > /dev/null
+seq 1 $COUNT | xargs -Iunused -P$PARALLEL_TASKS curl -s --retry 5 --retry-connrefused -XDELETE "$API_URL/sheds/$SHED_ID/wolf" > /dev/null
 end_ts=$(timestamp_nano)
 echo Calls took $(( ( $end_ts - $start_ts ) / 1000000 ))ms